@@ -95,7 +95,7 @@ def single_playout(kwargs: Dict[str, Any]) -> pd.DataFrame:
         regicide.players.MCTSPlayer(
             playouts=mcts_playouts,
             num_threads=num_threads,
-            use_heuristics=False,
+            rollout="uniform",
             policy_variation=policy_variation,
             deterministic_samples=deterministic_samples,
         ),
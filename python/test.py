@@ -30,7 +30,7 @@ class CustomPlayer:
 class CustomMCTSPlayer:
     def __init__(self) -> None:
         self.base = regicide.players.MCTSPlayer(
-            playouts=1000, use_heuristics=False, num_threads=7, policy_variation=4, deterministic_samples=100
+            playouts=1000, rollout="uniform", num_threads=7, policy_variation=4, deterministic_samples=100
         )
 
     def play(self, state):
@@ -56,7 +56,7 @@ players = [
     CustomMCTSPlayer(),
     CustomMCTSPlayer(),
     # CustomPlayer(),
-    # players.MCTSPlayer(playouts=1_000, num_threads=4, use_heuristics=False),
+    # players.MCTSPlayer(playouts=1_000, num_threads=4, rollout="uniform"),
     # players.InputPlayer(),
 ] 
 
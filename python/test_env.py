@@ -0,0 +1,19 @@
+import random
+
+import regicide
+
+
+def test_final_step():
+    game = regicide.RegicideGame.environment(2, seed=1337)
+    state = game.reset(seed=1337)
+    rng = random.Random(1337)
+    done = False
+    while not done:
+        assert state.result() is None
+        state, reward, done, info = game.step(rng.choice(state.action_space()))
+
+    # The observation after the last step is the final state of the game
+    assert info["result"] is not None
+    assert repr(state.result()) == repr(info["result"])
+    assert state.action_space() == []
+    assert state.reward() == game.reward()
@@ -0,0 +1,55 @@
+import pickle
+
+import regicide
+from regicide import actions, card, players
+
+
+def round_trip(x):
+    return pickle.loads(pickle.dumps(x))
+
+
+def settings(player):
+    """Constructor arguments of a player, without its seed."""
+    args = player.__reduce__()[1]
+    if isinstance(player, players.MCTSPlayer):
+        return args[:5] + args[6:]
+    if isinstance(player, (players.RandomPlayer, players.InputPlayer)):
+        return ()
+    return args[:-1]
+
+
+def uniform_evaluator(observations, masks):
+    return masks.astype("float32"), None
+
+
+def test_card():
+    c = card.Card(card.CardSuit.Hearts, card.CardValue.Jack)
+    assert round_trip(c) == c
+    assert round_trip(card.CardSuit.Clubs) == card.CardSuit.Clubs
+    assert round_trip(card.CardValue.Ace) == card.CardValue.Ace
+
+
+def test_action():
+    c = card.Card(card.CardSuit.Diamonds, card.CardValue.Six)
+    for action in [actions.ActionPlay(c), actions.ActionYield(), actions.ActionDiscard([c])]:
+        assert round_trip(action) == action
+
+
+def test_state():
+    state = regicide.RegicideGame.environment(2, seed=1337).reset(seed=1337)
+    assert round_trip(state).to_json() == state.to_json()
+
+
+def test_players():
+    for player in [
+        players.RandomPlayer(seed=1),
+        players.InputPlayer(),
+        players.MCTSPlayer(playouts=10, num_threads=1, rollout="greedy-kill", seed=1),
+        players.ISMCTSPlayer(playouts=10, rollout="immunity-aware", seed=1),
+        players.PuctPlayer(playouts=10, evaluator=uniform_evaluator, rollout="greedy-kill", seed=1),
+    ]:
+        copy = round_trip(player)
+        assert type(copy) is type(player)
+        assert settings(copy) == settings(player)
+        # Copies of the same player are seeded alike
+        assert round_trip(player).__reduce__() == copy.__reduce__()
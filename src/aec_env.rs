@@ -0,0 +1,279 @@
+use crate::game::action_index::ACTION_SPACE_SIZE;
+use crate::game::encoding::OBSERVATION_SIZE;
+use crate::game::player::PlayerId;
+use crate::game::rules::RuleSet;
+use crate::game::GameResult;
+use crate::python::{rules_or_official, PyGameResult, StateEnum};
+use numpy::PyArray1;
+use pyo3::exceptions::{PyKeyError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{IntoPyDict, PyDict};
+use std::collections::HashMap;
+
+/// A multi-agent environment following the agent-environment-cycle (AEC)
+/// API of PettingZoo. Agents are named `player_0`, `player_1` and so on, and
+/// act in the order given by the game. Regicide is cooperative, so every
+/// agent receives the reward of every action.
+///
+/// Observations are dicts with an encoded `observation` and an `action_mask`
+/// over the fixed action space, and actions are indices into that space.
+#[pyclass(module = "regicide")]
+pub struct RegicideAECEnv {
+    state: StateEnum,
+    rules: RuleSet,
+    result: Option<GameResult>,
+    #[pyo3(get)]
+    possible_agents: Vec<String>,
+    #[pyo3(get)]
+    agents: Vec<String>,
+    #[pyo3(get)]
+    agent_selection: String,
+    #[pyo3(get)]
+    rewards: HashMap<String, f32>,
+    #[pyo3(get, name = "_cumulative_rewards")]
+    cumulative_rewards: HashMap<String, f32>,
+    #[pyo3(get)]
+    terminations: HashMap<String, bool>,
+    #[pyo3(get)]
+    truncations: HashMap<String, bool>,
+}
+
+fn agent_name(id: PlayerId) -> String {
+    format!("player_{}", id.0)
+}
+
+impl RegicideAECEnv {
+    fn agent_id(&self, agent: &str) -> PyResult<PlayerId> {
+        self.possible_agents
+            .iter()
+            .position(|a| a == agent)
+            .map(PlayerId)
+            .ok_or_else(|| PyKeyError::new_err(format!("Unknown agent '{}'", agent)))
+    }
+
+    /// Removes the selected agent after the game has ended, as in PettingZoo's `_was_dead_step`.
+    fn dead_step(&mut self) {
+        let agent = self.agent_selection.clone();
+        self.agents.retain(|a| *a != agent);
+        self.rewards.remove(&agent);
+        self.cumulative_rewards.remove(&agent);
+        self.terminations.remove(&agent);
+        self.truncations.remove(&agent);
+
+        for reward in self.rewards.values_mut() {
+            *reward = 0.0;
+        }
+        if let Some(next_agent) = self.agents.first() {
+            self.agent_selection = next_agent.clone();
+        }
+    }
+}
+
+#[pymethods]
+impl RegicideAECEnv {
+    /// `rules` defaults to the official rules for the number of players.
+    #[new]
+    fn new(num_players: usize, seed: Option<u64>, rules: Option<RuleSet>) -> PyResult<Self> {
+        let rules = rules_or_official(num_players, rules)?;
+        let state = StateEnum::with_rules(num_players, seed, rules)?;
+        let possible_agents = (0..num_players)
+            .map(|id| agent_name(PlayerId(id)))
+            .collect();
+        let mut env = Self {
+            state,
+            rules,
+            result: None,
+            possible_agents,
+            agents: vec![],
+            agent_selection: String::new(),
+            rewards: HashMap::new(),
+            cumulative_rewards: HashMap::new(),
+            terminations: HashMap::new(),
+            truncations: HashMap::new(),
+        };
+        env.reset(seed, None)?;
+        Ok(env)
+    }
+
+    #[getter]
+    fn metadata<'py>(&self, py: Python<'py>) -> &'py PyDict {
+        [
+            ("name", "regicide_v0".into_py(py)),
+            ("is_parallelizable", false.into_py(py)),
+        ]
+        .into_py_dict(py)
+    }
+
+    #[getter]
+    fn num_agents(&self) -> usize {
+        self.agents.len()
+    }
+
+    #[getter]
+    fn max_num_agents(&self) -> usize {
+        self.possible_agents.len()
+    }
+
+    /// Holds the `result` of the game for every agent once it has ended.
+    #[getter]
+    fn infos<'py>(&self, py: Python<'py>) -> &'py PyDict {
+        let result = self.result.map(PyGameResult::from);
+        let infos = PyDict::new(py);
+        for agent in self.agents.iter() {
+            let info = match &result {
+                Some(result) => [("result", result.clone().into_py(py))].into_py_dict(py),
+                None => PyDict::new(py),
+            };
+            infos.set_item(agent, info).unwrap();
+        }
+        infos
+    }
+
+    fn reset(&mut self, seed: Option<u64>, options: Option<PyObject>) -> PyResult<()> {
+        // Options are part of the PettingZoo API but none are supported
+        let _ = options;
+
+        self.state = StateEnum::with_rules(self.possible_agents.len(), seed, self.rules)?;
+        self.result = None;
+        self.agents = self.possible_agents.clone();
+        self.agent_selection = agent_name(self.state.has_turn());
+        self.rewards = self.agents.iter().map(|a| (a.clone(), 0.0)).collect();
+        self.cumulative_rewards = self.rewards.clone();
+        self.terminations = self.agents.iter().map(|a| (a.clone(), false)).collect();
+        self.truncations = self.terminations.clone();
+        Ok(())
+    }
+
+    /// Take an action on behalf of `agent_selection`. Once the game has ended,
+    /// every agent must be stepped with `None` to be removed from `agents`.
+    fn step(&mut self, action: Option<usize>) -> PyResult<()> {
+        let agent = self.agent_selection.clone();
+        if self.terminations.get(&agent) == Some(&true) {
+            return match action {
+                None => {
+                    self.dead_step();
+                    Ok(())
+                }
+                Some(_) => Err(PyValueError::new_err(format!(
+                    "{} has terminated and can only be stepped with None",
+                    agent
+                ))),
+            };
+        }
+
+        let index = action
+            .ok_or_else(|| PyValueError::new_err(format!("{} must take an action", agent)))?;
+        let action = self.state.action_from_index(index).ok_or_else(|| {
+            PyKeyError::new_err(format!("Action index {} is not legal for {}", index, agent))
+        })?;
+
+        let (reward, result) = self.state.step(&action);
+        self.result = result;
+
+        self.cumulative_rewards.insert(agent, 0.0);
+        for (agent, cumulative_reward) in self.cumulative_rewards.iter_mut() {
+            self.rewards.insert(agent.clone(), reward as f32);
+            *cumulative_reward += reward as f32;
+        }
+        if result.is_some() {
+            for termination in self.terminations.values_mut() {
+                *termination = true;
+            }
+        }
+        self.agent_selection = agent_name(self.state.has_turn());
+        Ok(())
+    }
+
+    /// The observation of `agent` as a dict with the keys `observation` and
+    /// `action_mask`. The mask is all zeros when it is not the agent's turn.
+    fn observe<'py>(&self, py: Python<'py>, agent: &str) -> PyResult<&'py PyDict> {
+        let observation = self.state.observation(self.agent_id(agent)?);
+        let mask = observation
+            .action_mask()
+            .into_iter()
+            .map(|legal| legal as i8)
+            .collect();
+        Ok([
+            (
+                "observation",
+                PyArray1::from_vec(py, observation.encode()).to_object(py),
+            ),
+            (
+                "action_mask",
+                PyArray1::<i8>::from_vec(py, mask).to_object(py),
+            ),
+        ]
+        .into_py_dict(py))
+    }
+
+    /// Tuple in the form `(observation, cumulative_reward, termination, truncation, info)`
+    /// for `agent_selection`.
+    fn last(&self, py: Python, observe: Option<bool>) -> PyResult<PyObject> {
+        let agent = &self.agent_selection;
+        let observation = match observe.unwrap_or(true) {
+            true => self.observe(py, agent)?.to_object(py),
+            false => py.None(),
+        };
+        let info = self
+            .infos(py)
+            .get_item(agent)
+            .map_or(py.None(), |i| i.to_object(py));
+        Ok((
+            observation,
+            self.cumulative_rewards.get(agent).copied().unwrap_or(0.0),
+            self.terminations.get(agent).copied().unwrap_or(true),
+            self.truncations.get(agent).copied().unwrap_or(false),
+            info,
+        )
+            .into_py(py))
+    }
+
+    /// Requires `gymnasium` to be installed.
+    fn observation_space(&self, py: Python, agent: &str) -> PyResult<PyObject> {
+        self.agent_id(agent)?;
+        let spaces = py.import("gymnasium.spaces")?;
+        let np = py.import("numpy")?;
+        let observation = spaces.getattr("Box")?.call(
+            (f64::NEG_INFINITY, f64::INFINITY),
+            Some(
+                [
+                    ("shape", (OBSERVATION_SIZE,).into_py(py)),
+                    ("dtype", np.getattr("float32")?.into_py(py)),
+                ]
+                .into_py_dict(py),
+            ),
+        )?;
+        let action_mask = spaces.getattr("Box")?.call(
+            (0, 1),
+            Some(
+                [
+                    ("shape", (ACTION_SPACE_SIZE,).into_py(py)),
+                    ("dtype", np.getattr("int8")?.into_py(py)),
+                ]
+                .into_py_dict(py),
+            ),
+        )?;
+        let space = spaces.getattr("Dict")?.call1(([
+            ("observation", observation),
+            ("action_mask", action_mask),
+        ]
+        .into_py_dict(py),))?;
+        Ok(space.into_py(py))
+    }
+
+    /// Requires `gymnasium` to be installed.
+    fn action_space(&self, py: Python, agent: &str) -> PyResult<PyObject> {
+        self.agent_id(agent)?;
+        let spaces = py.import("gymnasium.spaces")?;
+        Ok(spaces
+            .getattr("Discrete")?
+            .call1((ACTION_SPACE_SIZE,))?
+            .into_py(py))
+    }
+
+    fn render(&self) -> String {
+        self.state.to_string()
+    }
+
+    fn close(&self) {}
+}
@@ -1,9 +1,17 @@
+use crate::game::Action;
 use std::error::Error;
 use std::fmt::Formatter;
 
 #[derive(Debug)]
 pub enum RegicideError {
     WrongNumberOfPlayers,
+    IllegalAction(Action),
+    GameHasEnded,
+    NotEnoughSteps,
+    InvalidNotation(String),
+    InvalidRules(String),
+    UnknownRollout(String),
+    NoPlayouts,
 }
 
 impl std::fmt::Display for RegicideError {
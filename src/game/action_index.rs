@@ -0,0 +1,126 @@
+use super::card::{CardValue, Hand, DISTINCT_CARDS};
+use super::observation::Observation;
+use super::state::State;
+use super::{Action, MAX_HAND_SIZE};
+
+/// Plays of a single card, indexed by `Card::index`.
+const PLAY_OFFSET: usize = 0;
+/// Animal combos, indexed by the suit of the Ace and the index of its companion.
+const ANIMAL_COMBO_OFFSET: usize = PLAY_OFFSET + DISTINCT_CARDS;
+/// Combos of Twos to Fives, indexed by value and a bitmask of the suits played.
+const COMBO_OFFSET: usize = ANIMAL_COMBO_OFFSET + 4 * (DISTINCT_CARDS - 1);
+/// Discards, indexed by a bitmask of the positions in the hand that are discarded.
+const DISCARD_OFFSET: usize = COMBO_OFFSET + 4 * 16;
+const YIELD_OFFSET: usize = DISCARD_OFFSET + (1 << MAX_HAND_SIZE);
+/// Changing to a player, indexed by the id of the player.
+const CHANGE_PLAYER_OFFSET: usize = YIELD_OFFSET + 1;
+const REFILL_HAND_OFFSET: usize = CHANGE_PLAYER_OFFSET + 4;
+
+/// Number of indices in the fixed action space. Not every index corresponds
+/// to an action that can occur in a game.
+pub const ACTION_SPACE_SIZE: usize = REFILL_HAND_OFFSET + 1;
+
+impl Action {
+    /// Map the action to a fixed index in `0..ACTION_SPACE_SIZE`.
+    ///
+    /// Discards are indexed by the positions of the discarded cards in `hand`,
+    /// which must be the hand of the player taking the action. Returns `None`
+    /// if the action cannot be indexed, e.g. if the discarded cards are not in `hand`.
+    pub fn index(&self, hand: &Hand) -> Option<usize> {
+        match self {
+            Action::Play(card) => Some(PLAY_OFFSET + card.index()),
+            Action::AnimalCombo(ace, companion) => {
+                if ace.value != CardValue::Ace || companion.value == CardValue::Jester {
+                    return None;
+                }
+                let suit = ace.suit.index()?;
+                Some(ANIMAL_COMBO_OFFSET + suit * (DISTINCT_CARDS - 1) + companion.index())
+            }
+            Action::Combo(cards) => {
+                let value = cards.first()?.value;
+                let value_index = match value {
+                    CardValue::Two | CardValue::Three | CardValue::Four | CardValue::Five => {
+                        value.rank()? - 1
+                    }
+                    _ => return None,
+                };
+                let mut suit_mask = 0;
+                for card in cards.iter() {
+                    if card.value != value {
+                        return None;
+                    }
+                    suit_mask |= 1 << card.suit.index()?;
+                }
+                Some(COMBO_OFFSET + value_index * 16 + suit_mask)
+            }
+            Action::Discard(cards) => {
+                let mut position_mask = 0;
+                for card in cards.iter() {
+                    // Identical cards (i.e. Jesters) are assigned to the first unused position
+                    let position = hand
+                        .iter()
+                        .enumerate()
+                        .position(|(i, c)| c == card && position_mask & (1 << i) == 0)?;
+                    position_mask |= 1 << position;
+                }
+                Some(DISCARD_OFFSET + position_mask)
+            }
+            Action::Yield => Some(YIELD_OFFSET),
+            Action::ChangePlayer(id) if id.0 < 4 => Some(CHANGE_PLAYER_OFFSET + id.0),
+            Action::ChangePlayer(_) => None,
+            Action::RefillHand => Some(REFILL_HAND_OFFSET),
+        }
+    }
+}
+
+impl<const N_PLAYERS: usize> State<N_PLAYERS> {
+    /// Index of `action` in the fixed action space, when taken in this state.
+    pub fn action_index(&self, action: &Action) -> Option<usize> {
+        action.index(&self.current_hand())
+    }
+
+    /// A mask of length `ACTION_SPACE_SIZE` that is `true` for the indices of legal actions.
+    pub fn action_mask(&self) -> Vec<bool> {
+        let mut mask = vec![false; ACTION_SPACE_SIZE];
+        for action in self.get_action_space() {
+            if let Some(index) = self.action_index(&action) {
+                mask[index] = true;
+            }
+        }
+        mask
+    }
+
+    /// The legal action with the given index, if there is one.
+    pub fn action_from_index(&self, index: usize) -> Option<Action> {
+        self.get_action_space()
+            .into_iter()
+            .find(|action| self.action_index(action) == Some(index))
+    }
+}
+
+impl Observation {
+    /// Index of `action` in the fixed action space, when taken by the observing player.
+    pub fn action_index(&self, action: &Action) -> Option<usize> {
+        action.index(&self.hand())
+    }
+
+    /// A mask of length `ACTION_SPACE_SIZE` that is `true` for the indices of
+    /// legal actions of the observing player.
+    pub fn action_mask(&self) -> Vec<bool> {
+        let mut mask = vec![false; ACTION_SPACE_SIZE];
+        for action in self.action_space() {
+            if let Some(index) = self.action_index(action) {
+                mask[index] = true;
+            }
+        }
+        mask
+    }
+
+    /// The legal action with the given index, if there is one.
+    pub fn action_from_index(&self, index: usize) -> Option<Action> {
+        self.action_space()
+            .iter()
+            .find(|action| self.action_index(action) == Some(index))
+            .copied()
+    }
+}
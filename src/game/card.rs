@@ -1,10 +1,19 @@
 use core::fmt;
 
+#[cfg(feature = "python")]
+use crate::error::RegicideError;
 use arrayvec::ArrayVecCopy;
 use colored::Colorize;
-use pyo3::{exceptions::PyTypeError, prelude::*};
+#[cfg(feature = "python")]
+use pyo3::{
+    exceptions::{PyTypeError, PyValueError},
+    prelude::*,
+};
 
 pub type CardVec = ArrayVecCopy<Card, 54>;
+
+/// Number of distinct cards in the game: 52 suited cards and the Jester.
+pub const DISTINCT_CARDS: usize = 53;
 pub type Hand = ArrayVecCopy<Card, { super::MAX_HAND_SIZE }>;
 
 pub trait FromCardIter {
@@ -25,14 +34,16 @@ impl<const N: usize> FromCardIter for ArrayVecCopy<Card, N> {
 }
 
 #[derive(Clone, Copy, Eq, PartialEq, Hash)]
-#[pyclass]
+#[cfg_attr(feature = "python", pyclass(module = "regicide.card"))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Card {
     pub suit: CardSuit,
     pub value: CardValue,
 }
 
 #[derive(Clone, Copy, Eq, PartialEq, Hash)]
-#[pyclass]
+#[cfg_attr(feature = "python", pyclass(module = "regicide.card"))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CardSuit {
     Spades,
     Hearts,
@@ -46,8 +57,33 @@ impl CardSuit {
         use CardSuit::*;
         [Spades, Hearts, Diamonds, Clubs]
     }
+
+    /// Position of the suit in `CardSuit::all()`. The suit of the Jester has no index.
+    pub fn index(&self) -> Option<usize> {
+        use CardSuit::*;
+        match self {
+            Spades => Some(0),
+            Hearts => Some(1),
+            Diamonds => Some(2),
+            Clubs => Some(3),
+            None => Option::None,
+        }
+    }
+
+    /// Name of the variant as seen from Python.
+    pub fn name(&self) -> &'static str {
+        use CardSuit::*;
+        match self {
+            Spades => "Spades",
+            Hearts => "Hearts",
+            Diamonds => "Diamonds",
+            Clubs => "Clubs",
+            None => "None",
+        }
+    }
 }
 
+#[cfg(feature = "python")]
 #[pymethods]
 impl CardSuit {
     #[staticmethod]
@@ -55,6 +91,11 @@ impl CardSuit {
     pub fn py_all() -> Vec<CardSuit> {
         Self::all().into()
     }
+
+    fn __reduce__(&self, py: Python) -> PyResult<(PyObject, (PyObject, &'static str))> {
+        let getattr = py.import("builtins")?.getattr("getattr")?;
+        Ok((getattr.into(), (py.get_type::<Self>().into(), self.name())))
+    }
 }
 
 pub trait AttackValue: Sized + Copy {
@@ -96,7 +137,8 @@ impl PartialOrd for CardSuit {
 }
 
 #[derive(Clone, Copy, Eq, PartialEq, Hash)]
-#[pyclass]
+#[cfg_attr(feature = "python", pyclass(module = "regicide.card"))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CardValue {
     Jester,
     Ace,
@@ -124,8 +166,51 @@ impl CardValue {
         use CardValue::*;
         [King, Queen, Jack]
     }
+
+    /// Rank of the value from Ace (0) to King (12). The Jester has no rank.
+    pub fn rank(&self) -> Option<usize> {
+        use CardValue::*;
+        match self {
+            Jester => None,
+            Ace => Some(0),
+            Two => Some(1),
+            Three => Some(2),
+            Four => Some(3),
+            Five => Some(4),
+            Six => Some(5),
+            Seven => Some(6),
+            Eight => Some(7),
+            Nine => Some(8),
+            Ten => Some(9),
+            Jack => Some(10),
+            Queen => Some(11),
+            King => Some(12),
+        }
+    }
+
+    /// Name of the variant as seen from Python.
+    pub fn name(&self) -> &'static str {
+        use CardValue::*;
+        match self {
+            Jester => "Jester",
+            Ace => "Ace",
+            Two => "Two",
+            Three => "Three",
+            Four => "Four",
+            Five => "Five",
+            Six => "Six",
+            Seven => "Seven",
+            Eight => "Eight",
+            Nine => "Nine",
+            Ten => "Ten",
+            Jack => "Jack",
+            Queen => "Queen",
+            King => "King",
+        }
+    }
 }
 
+#[cfg(feature = "python")]
 #[pymethods]
 impl CardValue {
     #[staticmethod]
@@ -139,19 +224,41 @@ impl CardValue {
     pub fn py_royals() -> Vec<CardValue> {
         Self::royals().into()
     }
+
+    fn __reduce__(&self, py: Python) -> PyResult<(PyObject, (PyObject, &'static str))> {
+        let getattr = py.import("builtins")?.getattr("getattr")?;
+        Ok((getattr.into(), (py.get_type::<Self>().into(), self.name())))
+    }
 }
 
+#[cfg(feature = "python")]
 #[pymethods]
 impl Card {
     #[new]
-    pub fn new(suit: CardSuit, value: CardValue) -> Self {
-        Self { suit, value }
+    fn py_new(suit: CardSuit, value: CardValue) -> Self {
+        Self::new(suit, value)
+    }
+
+    /// Parse a card written like `7H`, `AS` or `JK` for the Jester.
+    #[staticmethod]
+    fn parse(text: &str) -> PyResult<Card> {
+        text.parse()
+            .map_err(|e: RegicideError| PyValueError::new_err(e.to_string()))
+    }
+
+    /// The card in the notation understood by `Card.parse()`.
+    fn notation(&self) -> String {
+        self.to_string()
     }
 
     fn __str__(&self) -> String {
         format!("{:?}", self)
     }
 
+    fn __reduce__(&self, py: Python) -> (PyObject, (CardSuit, CardValue)) {
+        (py.get_type::<Self>().into(), (self.suit, self.value))
+    }
+
     fn __richcmp__(&self, other: &Self, op: pyo3::basic::CompareOp) -> PyResult<bool> {
         match op {
             pyo3::pyclass::CompareOp::Eq => Ok(self == other),
@@ -161,6 +268,21 @@ impl Card {
     }
 }
 
+impl Card {
+    pub fn new(suit: CardSuit, value: CardValue) -> Self {
+        Self { suit, value }
+    }
+
+    /// A unique index in `0..DISTINCT_CARDS` for every distinct card.
+    /// Suited cards are ordered by suit and then by rank, and the Jester is last.
+    pub fn index(&self) -> usize {
+        match (self.suit.index(), self.value.rank()) {
+            (Some(suit), Some(rank)) => suit * 13 + rank,
+            _ => DISTINCT_CARDS - 1,
+        }
+    }
+}
+
 impl AttackValue for Card {
     fn attack_value(&self) -> u16 {
         use CardValue::*;
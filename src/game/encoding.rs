@@ -0,0 +1,89 @@
+use super::card::{CardValue, DISTINCT_CARDS};
+use super::observation::Observation;
+use super::state::{ActionType, State};
+
+/// One slot for each suited card and one for each of the two Jesters.
+const HAND_FEATURES: usize = DISTINCT_CARDS + 1;
+/// One-hot encoding of the enemy card: 4 suits times 3 royals.
+const ENEMY_CARD_FEATURES: usize = 12;
+/// Enemy health, attack and whether the Jester has been applied.
+const ENEMY_STAT_FEATURES: usize = 3;
+/// Size of the discard pile and the castle deck.
+const PILE_FEATURES: usize = 2;
+/// One-hot encoding of the `ActionType` followed by the amount to discard.
+const PHASE_FEATURES: usize = 4;
+/// Number of consecutive yields.
+const YIELD_FEATURES: usize = 1;
+
+/// Length of the vector returned by `Observation::encode`.
+pub const OBSERVATION_SIZE: usize = HAND_FEATURES
+    + ENEMY_CARD_FEATURES
+    + ENEMY_STAT_FEATURES
+    + PILE_FEATURES
+    + PHASE_FEATURES
+    + YIELD_FEATURES;
+
+impl<const N_PLAYERS: usize> State<N_PLAYERS> {
+    /// Encodes the state as seen by the player who has the turn.
+    /// See `Observation::encode`.
+    pub fn encode(&self) -> Vec<f32> {
+        self.observation(self.has_turn()).encode()
+    }
+}
+
+impl Observation {
+    /// Encodes the observation into a flat vector of length `OBSERVATION_SIZE`.
+    /// The layout is:
+    ///
+    /// - `[0, 54)`: hand one-hot over the 52 suited cards followed by two Jester slots
+    /// - `[54, 66)`: enemy card one-hot over suit and royal (Jack, Queen, King)
+    /// - `[66, 69)`: enemy health, enemy attack and jester applied (0 or 1)
+    /// - `[69, 71)`: number of cards in the discard pile and enemies left in the castle deck
+    /// - `[71, 75)`: phase one-hot (play cards, discard, jester) and the amount to discard
+    /// - `[75, 76)`: number of consecutive yields
+    pub fn encode(&self) -> Vec<f32> {
+        let mut encoding = vec![0.0; OBSERVATION_SIZE];
+
+        let (hand, rest) = encoding.split_at_mut(HAND_FEATURES);
+        for card in self.hand().iter() {
+            match card.value {
+                CardValue::Jester if hand[DISTINCT_CARDS - 1] == 0.0 => {
+                    hand[DISTINCT_CARDS - 1] = 1.0
+                }
+                CardValue::Jester => hand[DISTINCT_CARDS] = 1.0,
+                _ => hand[card.index()] = 1.0,
+            }
+        }
+
+        let (enemy_card, rest) = rest.split_at_mut(ENEMY_CARD_FEATURES);
+        let (enemy_stats, rest) = rest.split_at_mut(ENEMY_STAT_FEATURES);
+        if let Some(enemy) = self.current_enemy() {
+            let card = enemy.card();
+            if let (Some(suit), Some(rank)) = (card.suit.index(), card.value.rank()) {
+                // Jack, Queen and King have ranks 10, 11 and 12
+                enemy_card[suit * 3 + rank - 10] = 1.0;
+            }
+            enemy_stats[0] = enemy.health() as f32;
+            enemy_stats[1] = enemy.attack_value() as f32;
+            enemy_stats[2] = enemy.jester_applied() as u8 as f32;
+        }
+
+        let (piles, rest) = rest.split_at_mut(PILE_FEATURES);
+        piles[0] = self.discard_pile().len() as f32;
+        piles[1] = self.castle_deck_size() as f32;
+
+        let (phase, rest) = rest.split_at_mut(PHASE_FEATURES);
+        match self.action_type() {
+            ActionType::PlayCards => phase[0] = 1.0,
+            ActionType::Discard(amount) => {
+                phase[1] = 1.0;
+                phase[3] = amount as f32;
+            }
+            ActionType::Jester => phase[2] = 1.0,
+        }
+
+        rest[0] = self.times_yielded() as f32;
+
+        encoding
+    }
+}
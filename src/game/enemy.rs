@@ -1,9 +1,12 @@
-use super::card::{AttackValue, Card, CardValue};
+use super::card::Card;
+use super::rules::{royal_index, RuleSet, OFFICIAL_ENEMY_ATTACK, OFFICIAL_ENEMY_HEALTH};
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
 use std::convert::TryInto;
 
-#[pyclass]
+#[cfg_attr(feature = "python", pyclass(module = "regicide"))]
 #[derive(Debug, Clone, Copy, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Enemy {
     card: Card,
     health: i8,
@@ -12,18 +15,30 @@ pub struct Enemy {
 }
 
 impl Enemy {
+    /// An enemy with the health and attack of the official rules.
     pub fn new(card: Card) -> Enemy {
-        use CardValue::*;
-        let health = match card.value {
-            Jack => 20,
-            Queen => 30,
-            King => 40,
-            _ => panic!("{:?} is not an enemy", card),
-        };
+        let royal = royal_index(card.value);
+        Self::with_stats(
+            card,
+            OFFICIAL_ENEMY_HEALTH[royal],
+            OFFICIAL_ENEMY_ATTACK[royal],
+        )
+    }
+
+    /// An enemy with the health and attack given by `rules`.
+    pub fn with_rules(card: Card, rules: &RuleSet) -> Enemy {
+        Self::with_stats(
+            card,
+            rules.enemy_health(card.value),
+            rules.enemy_attack(card.value),
+        )
+    }
+
+    fn with_stats(card: Card, health: i8, attack: u8) -> Enemy {
         Self {
             card,
             health,
-            attack: card.attack_value() as u8,
+            attack,
             jester_applied: false,
         }
     }
@@ -63,8 +78,32 @@ impl Enemy {
     }
 }
 
+#[cfg(feature = "python")]
 #[pymethods]
 impl Enemy {
+    /// Damage needed to defeat the enemy.
+    #[pyo3(name = "health")]
+    fn py_health(&self) -> i8 {
+        self.health
+    }
+
+    /// Attack after reductions from played Spades.
+    #[pyo3(name = "attack")]
+    fn py_attack(&self) -> u8 {
+        self.attack
+    }
+
+    #[pyo3(name = "card")]
+    fn py_card(&self) -> Card {
+        self.card
+    }
+
+    /// Whether a Jester has cancelled the suit power of the enemy.
+    #[pyo3(name = "jester_applied")]
+    fn py_jester_applied(&self) -> bool {
+        self.jester_applied
+    }
+
     fn __str__(&self) -> String {
         format!("{:?}", self)
     }
@@ -1,22 +1,46 @@
+pub mod action_index;
 pub mod card;
+pub mod encoding;
 pub mod enemy;
+pub mod notation;
+pub mod observation;
 pub mod player;
 pub mod policy;
+pub mod record;
+pub mod rollout;
+pub mod rules;
+#[cfg(feature = "serde")]
+mod serde_utils;
 pub mod state;
 pub mod table;
 
 use self::{card::Hand, player::PlayerId};
 use card::Card;
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
 use state::State;
 use std::fmt::Debug;
 
 pub const MAX_HAND_SIZE: usize = 8;
 
 #[derive(Debug, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GameResult {
-    Won,
+    /// Solo victories are rated by a `SoloOutcome`, other victories are not.
+    Won(Option<SoloOutcome>),
     Lost(u8),
 }
+
+/// The rating of a solo victory under the official rules, depending on how
+/// many times the hand was refilled (i.e. how many Jesters were used).
+#[cfg_attr(feature = "python", pyclass(module = "regicide"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SoloOutcome {
+    Gold,
+    Silver,
+    Bronze,
+}
 #[derive(Debug)]
 pub enum GameStatus<const N_PLAYERS: usize> {
     InProgress(State<N_PLAYERS>),
@@ -24,12 +48,18 @@ pub enum GameStatus<const N_PLAYERS: usize> {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Action {
     Play(Card),
     AnimalCombo(Card, Card),
-    Combo(arrayvec::ArrayVecCopy<Card, 4>),
+    Combo(
+        #[cfg_attr(feature = "serde", serde(with = "crate::game::serde_utils::array_vec"))]
+        arrayvec::ArrayVecCopy<Card, 4>,
+    ),
     Yield,
-    Discard(Hand),
+    Discard(
+        #[cfg_attr(feature = "serde", serde(with = "crate::game::serde_utils::array_vec"))] Hand,
+    ),
     ChangePlayer(PlayerId),
     RefillHand,
 }
@@ -38,4 +68,30 @@ impl GameResult {
     pub const fn max_score() -> u8 {
         12
     }
+
+    /// Number of enemies defeated during the game.
+    pub fn reward(&self) -> u8 {
+        match self {
+            GameResult::Won(_) => Self::max_score(),
+            GameResult::Lost(reward) => *reward,
+        }
+    }
+
+    pub fn solo_outcome(&self) -> Option<SoloOutcome> {
+        match self {
+            GameResult::Won(outcome) => *outcome,
+            GameResult::Lost(_) => None,
+        }
+    }
+}
+
+impl SoloOutcome {
+    /// Gold for no refills, Silver for one and Bronze for more.
+    pub fn from_refills_used(refills_used: u8) -> Self {
+        match refills_used {
+            0 => SoloOutcome::Gold,
+            1 => SoloOutcome::Silver,
+            _ => SoloOutcome::Bronze,
+        }
+    }
 }
@@ -0,0 +1,201 @@
+//! A compact textual notation for cards and actions that can be parsed back.
+//!
+//! Cards are written as their value followed by their suit, e.g. `7H`, `AS`
+//! or `10D`, and the Jester is written as `JK`. Actions are written as:
+//!
+//! - `7H` to play a single card
+//! - `AS+9C` for an animal combo, and `5H+5S` for a combo
+//! - `discard[3D,5S]`
+//! - `yield`, `refill` and `to:P2` to change player with the Jester
+
+use super::card::{Card, CardSuit, CardValue, FromCardIter};
+use super::observation::Observation;
+use super::player::PlayerId;
+use super::state::State;
+use super::Action;
+use crate::error::RegicideError;
+use arrayvec::ArrayVecCopy;
+use itertools::Itertools;
+use std::fmt;
+use std::str::FromStr;
+
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use CardValue::*;
+        let value = match self.value {
+            Jester => return write!(f, "JK"),
+            Ace => "A",
+            Two => "2",
+            Three => "3",
+            Four => "4",
+            Five => "5",
+            Six => "6",
+            Seven => "7",
+            Eight => "8",
+            Nine => "9",
+            Ten => "10",
+            Jack => "J",
+            Queen => "Q",
+            King => "K",
+        };
+        let suit = match self.suit {
+            CardSuit::Spades => "S",
+            CardSuit::Hearts => "H",
+            CardSuit::Diamonds => "D",
+            CardSuit::Clubs => "C",
+            CardSuit::None => "",
+        };
+        write!(f, "{}{}", value, suit)
+    }
+}
+
+impl FromStr for Card {
+    type Err = RegicideError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        use CardValue::*;
+        let invalid = || RegicideError::InvalidNotation(text.to_string());
+
+        let text_upper = text.trim().to_uppercase();
+        if text_upper == "JK" {
+            return Ok(Card::new(CardSuit::None, Jester));
+        }
+        if !text_upper.is_ascii() || text_upper.len() < 2 {
+            return Err(invalid());
+        }
+
+        let (value, suit) = text_upper.split_at(text_upper.len() - 1);
+        let value = match value {
+            "A" => Ace,
+            "2" => Two,
+            "3" => Three,
+            "4" => Four,
+            "5" => Five,
+            "6" => Six,
+            "7" => Seven,
+            "8" => Eight,
+            "9" => Nine,
+            "10" | "T" => Ten,
+            "J" => Jack,
+            "Q" => Queen,
+            "K" => King,
+            _ => return Err(invalid()),
+        };
+        let suit = match suit {
+            "S" => CardSuit::Spades,
+            "H" => CardSuit::Hearts,
+            "D" => CardSuit::Diamonds,
+            "C" => CardSuit::Clubs,
+            _ => return Err(invalid()),
+        };
+        Ok(Card::new(suit, value))
+    }
+}
+
+/// Write cards separated by commas, e.g. `3D,5S`. `Hand` is an alias for a
+/// foreign type, so it cannot implement `Display` itself.
+pub fn format_cards(cards: &[Card]) -> String {
+    cards.iter().join(",")
+}
+
+/// Parse cards written by `format_cards`.
+pub fn parse_cards<const CAP: usize>(text: &str) -> Result<ArrayVecCopy<Card, CAP>, RegicideError> {
+    parse_card_list(text, ',')
+}
+
+fn parse_card_list<const CAP: usize>(
+    text: &str,
+    separator: char,
+) -> Result<ArrayVecCopy<Card, CAP>, RegicideError> {
+    if text.trim().is_empty() {
+        return Ok(ArrayVecCopy::new());
+    }
+    let cards = text
+        .split(separator)
+        .map(str::parse)
+        .collect::<Result<Vec<Card>, _>>()?;
+    if cards.len() > CAP {
+        return Err(RegicideError::InvalidNotation(text.to_string()));
+    }
+    Ok(ArrayVecCopy::from_card_iter(cards))
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Play(card) => write!(f, "{}", card),
+            Action::AnimalCombo(ace, companion) => write!(f, "{}+{}", ace, companion),
+            Action::Combo(cards) => write!(f, "{}", cards.iter().join("+")),
+            Action::Yield => write!(f, "yield"),
+            Action::Discard(cards) => write!(f, "discard[{}]", format_cards(cards)),
+            Action::ChangePlayer(id) => write!(f, "to:P{}", id.0),
+            Action::RefillHand => write!(f, "refill"),
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = RegicideError;
+
+    /// Parses the notation of an action. The order of the cards in a combo or
+    /// a discard is kept as written, so the parsed action may only equal a
+    /// legal action up to the order of its cards. See `State::action_index`.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || RegicideError::InvalidNotation(text.to_string());
+        let text = text.trim();
+        let lowercase = text.to_lowercase();
+
+        if lowercase == "yield" {
+            return Ok(Action::Yield);
+        }
+        if lowercase == "refill" {
+            return Ok(Action::RefillHand);
+        }
+        if let Some(id) = lowercase.strip_prefix("to:p") {
+            let id = id.parse().map_err(|_| invalid())?;
+            return Ok(Action::ChangePlayer(PlayerId(id)));
+        }
+        if let Some(cards) = lowercase.strip_prefix("discard[") {
+            let cards = cards.strip_suffix(']').ok_or_else(invalid)?;
+            return Ok(Action::Discard(parse_cards(cards)?));
+        }
+
+        let cards = parse_card_list::<4>(text, '+')?;
+        match cards.as_slice() {
+            [] => Err(invalid()),
+            [card] => Ok(Action::Play(*card)),
+            [c1, c2] if c1.value == CardValue::Ace || c2.value == CardValue::Ace => {
+                // The ace comes first, and the ace of the highest suit when there are two
+                let (ace, companion) = match (c1.value, c2.value) {
+                    (CardValue::Ace, CardValue::Ace) if c2.suit > c1.suit => (c2, c1),
+                    (CardValue::Ace, _) => (c1, c2),
+                    _ => (c2, c1),
+                };
+                Ok(Action::AnimalCombo(*ace, *companion))
+            }
+            _ => Ok(Action::Combo(cards)),
+        }
+    }
+}
+
+impl<const N_PLAYERS: usize> State<N_PLAYERS> {
+    /// Parse the notation of a legal action. Cards are put in the same order
+    /// as in `get_action_space`, regardless of the order they were written in.
+    pub fn parse_action(&self, text: &str) -> Result<Action, RegicideError> {
+        let action: Action = text.parse()?;
+        self.action_index(&action)
+            .and_then(|index| self.action_from_index(index))
+            .ok_or(RegicideError::IllegalAction(action))
+    }
+}
+
+impl Observation {
+    /// Parse the notation of a legal action of the observing player.
+    /// See `State::parse_action`.
+    pub fn parse_action(&self, text: &str) -> Result<Action, RegicideError> {
+        let action: Action = text.parse()?;
+        self.action_index(&action)
+            .and_then(|index| self.action_from_index(index))
+            .ok_or(RegicideError::IllegalAction(action))
+    }
+}
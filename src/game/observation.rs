@@ -0,0 +1,144 @@
+use super::card::{Card, CardVec, Hand};
+use super::enemy::Enemy;
+use super::player::PlayerId;
+use super::state::{ActionType, State};
+use super::Action;
+use itertools::Itertools;
+
+/// The parts of a `State` that a single player is allowed to see under the
+/// official rules. The hands of other players, the order of the Tavern deck
+/// and the order of the Castle deck are hidden.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observation {
+    player: PlayerId,
+    has_turn: PlayerId,
+    hand: Hand,
+    hand_sizes: Vec<usize>,
+    enemy: Option<Enemy>,
+    castle_deck: Vec<Card>,
+    tavern_deck_size: usize,
+    discard_pile: CardVec,
+    attack_cards: CardVec,
+    action_type: ActionType,
+    times_yielded: usize,
+    level: u8,
+    hand_refills_left: u8,
+    action_space: Vec<Action>,
+}
+
+impl<const N_PLAYERS: usize> State<N_PLAYERS> {
+    /// What `player` is able to observe of the state.
+    pub fn observation(&self, player: PlayerId) -> Observation {
+        let action_space = match player == self.has_turn() {
+            true => self.get_action_space(),
+            false => vec![],
+        };
+        // Only the order of the Castle deck is hidden, not which enemies are left in it
+        let castle_deck = self
+            .table()
+            .castle_deck()
+            .iter()
+            .map(|enemy| *enemy.card())
+            .sorted_by_key(Card::index)
+            .collect();
+
+        Observation {
+            player,
+            has_turn: self.has_turn(),
+            hand: self.players()[player.0].hand,
+            hand_sizes: self.players().iter().map(|p| p.hand.len()).collect(),
+            enemy: self.current_enemy().copied(),
+            castle_deck,
+            tavern_deck_size: self.table().tavern_deck().len(),
+            discard_pile: *self.table().discard_pile(),
+            attack_cards: *self.table().attack_cards(),
+            action_type: self.action_type(),
+            times_yielded: self.times_yielded(),
+            level: self.reward(),
+            hand_refills_left: self.hand_refills_left(),
+            action_space,
+        }
+    }
+}
+
+impl Observation {
+    /// The player making the observation.
+    pub fn player(&self) -> PlayerId {
+        self.player
+    }
+
+    pub fn has_turn(&self) -> PlayerId {
+        self.has_turn
+    }
+
+    /// The hand of the observing player.
+    pub fn hand(&self) -> Hand {
+        self.hand
+    }
+
+    /// Number of cards in the hand of every player, ordered by id.
+    pub fn hand_sizes(&self) -> &[usize] {
+        &self.hand_sizes
+    }
+
+    pub fn current_enemy(&self) -> Option<&Enemy> {
+        self.enemy.as_ref()
+    }
+
+    /// Number of enemies left in the Castle deck, including the current enemy.
+    pub fn castle_deck_size(&self) -> usize {
+        self.castle_deck.len()
+    }
+
+    /// Cards of the enemies left in the Castle deck, including the current
+    /// enemy, ordered by suit and value rather than by their position in the deck.
+    pub fn castle_deck(&self) -> &[Card] {
+        &self.castle_deck
+    }
+
+    pub fn tavern_deck_size(&self) -> usize {
+        self.tavern_deck_size
+    }
+
+    pub fn discard_pile(&self) -> &CardVec {
+        &self.discard_pile
+    }
+
+    /// Cards played against the current enemy.
+    pub fn attack_cards(&self) -> &CardVec {
+        &self.attack_cards
+    }
+
+    pub fn action_type(&self) -> ActionType {
+        self.action_type
+    }
+
+    pub fn times_yielded(&self) -> usize {
+        self.times_yielded
+    }
+
+    pub fn reward(&self) -> u8 {
+        self.level
+    }
+
+    pub fn hand_refills_left(&self) -> u8 {
+        self.hand_refills_left
+    }
+
+    /// Legal actions for the observing player. Empty if it is not their turn.
+    pub fn action_space(&self) -> &[Action] {
+        &self.action_space
+    }
+}
+
+impl std::fmt::Display for Observation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            format!("Player: {:?}\n", self.player()),
+            format!("Hand:   {:?}\n", self.hand()),
+            format!("Enemy:  {:?}", self.current_enemy()),
+        )
+    }
+}
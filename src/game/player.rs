@@ -1,8 +1,10 @@
 use crate::game::card::{AttackSum, Hand};
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
 
-#[pyclass]
+#[cfg_attr(feature = "python", pyclass(module = "regicide"))]
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PlayerId(pub usize);
 
 impl PlayerId {
@@ -12,9 +14,11 @@ impl PlayerId {
 }
 
 #[derive(Debug, Clone, Copy, Hash)]
-#[pyclass]
+#[cfg_attr(feature = "python", pyclass(module = "regicide"))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Player {
     id: PlayerId,
+    #[cfg_attr(feature = "serde", serde(with = "crate::game::serde_utils::array_vec"))]
     pub hand: Hand,
 }
 
@@ -1,7 +1,11 @@
-use mcts::{
-    tree_policy::{PolicyRng, TreePolicy},
-    MoveInfo, SearchHandle, MCTS,
-};
+use mcts::{tree_policy::TreePolicy, MoveInfo, SearchHandle, MCTS};
+use rand::rngs::StdRng;
+use rand::Rng;
+
+/// A search that can create a random number generator for each of its threads.
+pub trait ThreadRngSource {
+    fn new_thread_rng(&self) -> StdRng;
+}
 
 #[derive(Clone, Debug)]
 pub enum MyPolicy {
@@ -11,8 +15,9 @@ pub enum MyPolicy {
     UCTVariation4 { max_score: f64 },
 }
 
-impl<Spec: MCTS<TreePolicy = Self>> TreePolicy<Spec> for MyPolicy {
-    type ThreadLocalData = PolicyRng;
+impl<Spec: MCTS<TreePolicy = Self> + ThreadRngSource> TreePolicy<Spec> for MyPolicy {
+    /// Breaks ties between children. Created on first use by `Spec`.
+    type ThreadLocalData = Option<StdRng>;
     type MoveEvaluation = ();
 
     fn choose_child<'a, MoveIter>(
@@ -31,51 +36,79 @@ impl<Spec: MCTS<TreePolicy = Self>> TreePolicy<Spec> for MyPolicy {
         // number of child nodes that don't have any children?
         let leaf_node_count = moves.clone().filter(|x| x.child().is_none()).count() as f64 + 1.0;
 
-        handle
-            .thread_data()
-            .policy_data
-            .select_by_key(moves, |mov| {
-                // number of node obervations
-                let n_i = mov.visits() as f64;
+        if handle.thread_data().policy_data.is_none() {
+            let rng = handle.mcts().new_thread_rng();
+            handle.thread_data().policy_data = Some(rng);
+        }
+        let rng = handle.thread_data().policy_data.as_mut().unwrap();
 
-                // mean action value
-                let mu_i = mov.sum_rewards() as f64 / n_i as f64;
+        select_by_key(rng, moves, |mov| {
+            // number of node obervations
+            let n_i = mov.visits() as f64;
 
-                // Aliases to simplify math below
-                let sqrt = f64::sqrt;
-                let ln = f64::ln;
+            // mean action value
+            let mu_i = mov.sum_rewards() as f64 / n_i as f64;
 
-                match self {
-                    // Avoid dividing by 0
-                    _ if n_i == 0.0 => std::f64::INFINITY,
+            // Aliases to simplify math below
+            let sqrt = f64::sqrt;
+            let ln = f64::ln;
 
-                    MyPolicy::UCTBase {
-                        exploration_constant,
-                    } => {
-                        let explore_term = sqrt(2.0 * ln(N_i) / n_i);
-                        mu_i + exploration_constant * explore_term
-                    }
+            match self {
+                // Avoid dividing by 0
+                _ if n_i == 0.0 => std::f64::INFINITY,
 
-                    MyPolicy::UCTVariation2 { max_score, delta } => {
-                        let beta = |n_i, delta| {
-                            ln(leaf_node_count / delta)
-                                + 3.0 * ln(ln(leaf_node_count / delta))
-                                + 3.0 / 2.0 * ln(ln(n_i) + 1.0)
-                        };
-                        mu_i + max_score * sqrt(beta(n_i, delta) / (2.0 * n_i))
-                    }
+                MyPolicy::UCTBase {
+                    exploration_constant,
+                } => {
+                    let explore_term = sqrt(2.0 * ln(N_i) / n_i);
+                    mu_i + exploration_constant * explore_term
+                }
 
-                    MyPolicy::UCTVariation3 { max_score, delta } => {
-                        let numerator = (1.0 + 1.0 / n_i) * ln(sqrt(n_i + 1.0) / delta);
-                        mu_i + max_score * sqrt(numerator / (2.0 * n_i))
-                    }
+                MyPolicy::UCTVariation2 { max_score, delta } => {
+                    let beta = |n_i, delta| {
+                        ln(leaf_node_count / delta)
+                            + 3.0 * ln(ln(leaf_node_count / delta))
+                            + 3.0 / 2.0 * ln(ln(n_i) + 1.0)
+                    };
+                    mu_i + max_score * sqrt(beta(n_i, delta) / (2.0 * n_i))
+                }
 
-                    MyPolicy::UCTVariation4 { max_score } => {
-                        let numerator = ln(N_i) + (3.0 * ln(ln(N_i) + 1.0));
-                        mu_i + max_score * sqrt(numerator / (2.0 * n_i))
-                    }
+                MyPolicy::UCTVariation3 { max_score, delta } => {
+                    let numerator = (1.0 + 1.0 / n_i) * ln(sqrt(n_i + 1.0) / delta);
+                    mu_i + max_score * sqrt(numerator / (2.0 * n_i))
                 }
-            })
-            .unwrap()
+
+                MyPolicy::UCTVariation4 { max_score } => {
+                    let numerator = ln(N_i) + (3.0 * ln(ln(N_i) + 1.0));
+                    mu_i + max_score * sqrt(numerator / (2.0 * n_i))
+                }
+            }
+        })
+        .unwrap()
+    }
+}
+
+/// The element with the highest key, with ties broken uniformly at random.
+fn select_by_key<T, Iter, KeyFn>(rng: &mut StdRng, elements: Iter, mut key_fn: KeyFn) -> Option<T>
+where
+    Iter: Iterator<Item = T>,
+    KeyFn: FnMut(&T) -> f64,
+{
+    let mut choice = None;
+    let mut num_optimal: u32 = 0;
+    let mut best_so_far = std::f64::NEG_INFINITY;
+    for element in elements {
+        let score = key_fn(&element);
+        if score > best_so_far {
+            choice = Some(element);
+            num_optimal = 1;
+            best_so_far = score;
+        } else if score == best_so_far {
+            num_optimal += 1;
+            if rng.gen_range(0..num_optimal) == 0 {
+                choice = Some(element);
+            }
+        }
     }
+    choice
 }
@@ -0,0 +1,237 @@
+use super::card::{Card, Hand};
+use super::enemy::Enemy;
+use super::player::PlayerId;
+use super::rules::RuleSet;
+use super::state::State;
+use super::{Action, GameResult, GameStatus};
+use crate::error::RegicideError;
+#[cfg(feature = "python")]
+use crate::python::{PyAction, PyGameResult, PyState, StateEnum};
+#[cfg(feature = "python")]
+use pyo3::exceptions::PyValueError;
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+
+/// Everything that happened in a game. Since a game is fully determined by
+/// its seed and the actions taken, any intermediate `State` can be
+/// reconstructed with `GameRecord::replay`.
+#[cfg_attr(feature = "python", pyclass(module = "regicide"))]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameRecord {
+    num_players: usize,
+    seed: u64,
+    rules: RuleSet,
+    steps: Vec<RecordStep>,
+    result: Option<GameResult>,
+}
+
+/// A single action along with what it caused.
+#[cfg_attr(feature = "python", pyclass(module = "regicide"))]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecordStep {
+    player: PlayerId,
+    action: Action,
+    /// The enemy after the action. `None` if the game ended.
+    enemy: Option<Enemy>,
+    cards_drawn: Vec<(PlayerId, Card)>,
+}
+
+impl GameRecord {
+    /// An empty record of a game created with `State::with_rules(Some(seed), rules)`.
+    pub fn new(num_players: usize, seed: u64, rules: RuleSet) -> Self {
+        Self {
+            num_players,
+            seed,
+            rules,
+            steps: vec![],
+            result: None,
+        }
+    }
+
+    pub fn push(&mut self, step: RecordStep) {
+        self.steps.push(step);
+    }
+
+    pub fn finish(&mut self, result: GameResult) {
+        self.result = Some(result);
+    }
+
+    pub fn num_players(&self) -> usize {
+        self.num_players
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    pub fn steps(&self) -> &[RecordStep] {
+        &self.steps
+    }
+
+    pub fn result(&self) -> Option<GameResult> {
+        self.result
+    }
+
+    /// Re-simulate the game from its seed and return the state after the
+    /// first `num_steps` actions.
+    pub fn replay<const N_PLAYERS: usize>(
+        &self,
+        num_steps: usize,
+    ) -> Result<State<N_PLAYERS>, RegicideError> {
+        if N_PLAYERS != self.num_players {
+            return Err(RegicideError::WrongNumberOfPlayers);
+        }
+        if num_steps > self.steps.len() {
+            return Err(RegicideError::NotEnoughSteps);
+        }
+
+        let mut state = State::with_rules(Some(self.seed), self.rules)?;
+        for step in self.steps.iter().take(num_steps) {
+            if !state.get_action_space().contains(&step.action) {
+                return Err(RegicideError::IllegalAction(step.action));
+            }
+            state = match state.take_action(&step.action) {
+                GameStatus::InProgress(next_state) => next_state,
+                GameStatus::HasEnded(_) => return Err(RegicideError::GameHasEnded),
+            };
+        }
+        Ok(state)
+    }
+}
+
+impl RecordStep {
+    /// `hands_before` and `hands_after` are the hands of every player, ordered
+    /// by id, before and after the action. `hands_after` is `None` if the
+    /// action ended the game.
+    pub fn new(
+        player: PlayerId,
+        action: Action,
+        enemy: Option<Enemy>,
+        hands_before: &[Hand],
+        hands_after: Option<&[Hand]>,
+    ) -> Self {
+        let mut cards_drawn = vec![];
+        for (id, (before, after)) in hands_before
+            .iter()
+            .zip(hands_after.unwrap_or_default())
+            .enumerate()
+        {
+            let mut before = before.to_vec();
+            for card in after.iter() {
+                match before.iter().position(|c| c == card) {
+                    Some(i) => {
+                        before.swap_remove(i);
+                    }
+                    None => cards_drawn.push((PlayerId(id), *card)),
+                }
+            }
+        }
+
+        Self {
+            player,
+            action,
+            enemy,
+            cards_drawn,
+        }
+    }
+
+    pub fn player(&self) -> PlayerId {
+        self.player
+    }
+
+    pub fn action(&self) -> Action {
+        self.action
+    }
+
+    pub fn enemy(&self) -> Option<&Enemy> {
+        self.enemy.as_ref()
+    }
+
+    /// Cards that were added to the hand of each player by the action.
+    pub fn cards_drawn(&self) -> &[(PlayerId, Card)] {
+        &self.cards_drawn
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl GameRecord {
+    #[pyo3(name = "num_players")]
+    fn py_num_players(&self) -> usize {
+        self.num_players
+    }
+
+    #[pyo3(name = "seed")]
+    fn py_seed(&self) -> u64 {
+        self.seed
+    }
+
+    #[pyo3(name = "rules")]
+    fn py_rules(&self) -> RuleSet {
+        self.rules
+    }
+
+    #[pyo3(name = "steps")]
+    fn py_steps(&self) -> Vec<RecordStep> {
+        self.steps.clone()
+    }
+
+    /// `None` while the game is in progress.
+    #[pyo3(name = "result")]
+    fn py_result(&self) -> Option<PyGameResult> {
+        self.result.map(PyGameResult::from)
+    }
+
+    /// The state after the first `step` actions of the game.
+    fn state_at(&self, step: usize) -> PyResult<PyState> {
+        let state_enum = match self.num_players {
+            1 => self.replay::<1>(step).map(StateEnum::from),
+            2 => self.replay::<2>(step).map(StateEnum::from),
+            3 => self.replay::<3>(step).map(StateEnum::from),
+            4 => self.replay::<4>(step).map(StateEnum::from),
+            _ => Err(RegicideError::WrongNumberOfPlayers),
+        }
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(PyState { state_enum })
+    }
+
+    fn __len__(&self) -> usize {
+        self.steps.len()
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl RecordStep {
+    #[pyo3(name = "player")]
+    fn py_player(&self) -> PlayerId {
+        self.player
+    }
+
+    #[pyo3(name = "action")]
+    fn py_action(&self) -> PyAction {
+        self.action.into()
+    }
+
+    /// The enemy after the action, or `None` if the game ended.
+    #[pyo3(name = "enemy")]
+    fn py_enemy(&self) -> Option<Enemy> {
+        self.enemy
+    }
+
+    /// List of tuples in the form `(player, card)`.
+    #[pyo3(name = "cards_drawn")]
+    fn py_cards_drawn(&self) -> Vec<(PlayerId, Card)> {
+        self.cards_drawn.clone()
+    }
+
+    fn __str__(&self) -> String {
+        format!("{:?}", self)
+    }
+}
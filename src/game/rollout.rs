@@ -0,0 +1,243 @@
+use super::card::{AttackSum, Card, CardSuit};
+use super::state::{ActionType, State};
+use super::Action;
+use crate::error::RegicideError;
+use itertools::Itertools;
+use rand::prelude::{SliceRandom, StdRng};
+use rand::Rng;
+use std::str::FromStr;
+
+/// Policies choosing the actions of every player during a rollout, see
+/// `State::rollout`. Apart from `Uniform`, each policy prefers some of the
+/// legal actions and chooses randomly among them, falling back to every legal
+/// action when it has no preference.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rollout {
+    /// Uniformly random actions.
+    Uniform,
+    /// Never discard Diamonds when there is another way to survive an attack.
+    NoDiamondDiscards,
+    /// Defeat the enemy whenever possible, preferring an exact kill and
+    /// otherwise the smallest overkill.
+    GreedyKill,
+    /// Survive attacks by discarding as little attack value as possible.
+    MinimalDamageDiscard,
+    /// Avoid playing cards whose suit power is blocked by the enemy's immunity.
+    ImmunityAware,
+    /// A uniformly random action with the given probability, and otherwise an
+    /// action preferred by `GreedyKill`, `ImmunityAware` and
+    /// `MinimalDamageDiscard` combined.
+    EpsilonGreedy(f64),
+}
+
+/// Exploration rate of `epsilon-greedy` when none is given.
+const DEFAULT_EPSILON: f64 = 0.1;
+
+impl Rollout {
+    /// Choose one of `actions`, the legal actions in `state`. `actions` is never empty.
+    pub fn choose<const N_PLAYERS: usize>(
+        &self,
+        state: &State<N_PLAYERS>,
+        actions: Vec<Action>,
+        rng: &mut StdRng,
+    ) -> Action {
+        let preferred = match self {
+            Rollout::Uniform => actions,
+            Rollout::NoDiamondDiscards => no_diamond_discards(actions),
+            Rollout::GreedyKill => greedy_kill(state, actions),
+            Rollout::MinimalDamageDiscard => minimal_damage_discard(state, actions),
+            Rollout::ImmunityAware => immunity_aware(state, actions),
+            Rollout::EpsilonGreedy(epsilon) if rng.gen_bool(epsilon.clamp(0.0, 1.0)) => actions,
+            Rollout::EpsilonGreedy(_) => {
+                let actions = greedy_kill(state, actions);
+                let actions = immunity_aware(state, actions);
+                minimal_damage_discard(state, actions)
+            }
+        };
+        *preferred
+            .choose(rng)
+            .expect("No actions available to choose from")
+    }
+}
+
+/// `preferred` if it is not empty, and otherwise every action.
+fn prefer(actions: Vec<Action>, preferred: Vec<Action>) -> Vec<Action> {
+    match preferred.is_empty() {
+        true => actions,
+        false => preferred,
+    }
+}
+
+/// Cards played to attack the enemy by `action`.
+fn played_cards(action: &Action) -> Vec<Card> {
+    match action {
+        Action::Play(card) => vec![*card],
+        Action::AnimalCombo(card1, card2) => vec![*card1, *card2],
+        Action::Combo(cards) => cards.iter().copied().collect(),
+        _ => vec![],
+    }
+}
+
+/// Whether the suit power of `suit` is blocked by the current enemy.
+fn is_immune<const N_PLAYERS: usize>(state: &State<N_PLAYERS>, suit: CardSuit) -> bool {
+    match state.current_enemy() {
+        Some(enemy) => enemy.card().suit == suit && !enemy.jester_applied(),
+        None => false,
+    }
+}
+
+/// Damage dealt to the current enemy by playing `cards`.
+fn damage<const N_PLAYERS: usize>(state: &State<N_PLAYERS>, cards: Vec<Card>) -> u16 {
+    let double =
+        cards.iter().any(|card| card.suit == CardSuit::Clubs) && !is_immune(state, CardSuit::Clubs);
+    match double {
+        true => 2 * cards.attack_sum(),
+        false => cards.attack_sum(),
+    }
+}
+
+fn no_diamond_discards(actions: Vec<Action>) -> Vec<Action> {
+    let preferred = actions
+        .iter()
+        .filter(|action| match action {
+            Action::Discard(cards) => cards.iter().all(|card| card.suit != CardSuit::Diamonds),
+            _ => true,
+        })
+        .copied()
+        .collect_vec();
+    prefer(actions, preferred)
+}
+
+fn greedy_kill<const N_PLAYERS: usize>(
+    state: &State<N_PLAYERS>,
+    actions: Vec<Action>,
+) -> Vec<Action> {
+    let health = match (state.action_type(), state.current_enemy()) {
+        (ActionType::PlayCards, Some(enemy)) => enemy.health().max(0) as u16,
+        _ => return actions,
+    };
+    // An exact kill puts the enemy on top of the Tavern deck, so it comes first
+    let kills = actions
+        .iter()
+        .map(|action| (*action, damage(state, played_cards(action))))
+        .filter(|&(_, damage)| damage > 0 && damage >= health)
+        .map(|(action, damage)| (action, (damage != health, damage)))
+        .collect_vec();
+    let preferred = match kills.iter().map(|&(_, key)| key).min() {
+        Some(best) => kills
+            .into_iter()
+            .filter(|&(_, key)| key == best)
+            .map(|(action, _)| action)
+            .collect(),
+        None => vec![],
+    };
+    prefer(actions, preferred)
+}
+
+fn minimal_damage_discard<const N_PLAYERS: usize>(
+    state: &State<N_PLAYERS>,
+    actions: Vec<Action>,
+) -> Vec<Action> {
+    if !matches!(state.action_type(), ActionType::Discard(_)) {
+        return actions;
+    }
+    let discarded = |action: &Action| match action {
+        Action::Discard(cards) => Some(cards.attack_sum()),
+        _ => None,
+    };
+    let preferred = match actions.iter().filter_map(discarded).min() {
+        Some(least) => actions
+            .iter()
+            .filter(|action| discarded(action) == Some(least))
+            .copied()
+            .collect(),
+        None => vec![],
+    };
+    prefer(actions, preferred)
+}
+
+fn immunity_aware<const N_PLAYERS: usize>(
+    state: &State<N_PLAYERS>,
+    actions: Vec<Action>,
+) -> Vec<Action> {
+    if !matches!(state.action_type(), ActionType::PlayCards) {
+        return actions;
+    }
+    let preferred = actions
+        .iter()
+        .filter(|action| {
+            let cards = played_cards(action);
+            !cards.is_empty() && cards.iter().all(|card| !is_immune(state, card.suit))
+        })
+        .copied()
+        .collect_vec();
+    prefer(actions, preferred)
+}
+
+impl std::fmt::Display for Rollout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rollout::Uniform => write!(f, "uniform"),
+            Rollout::NoDiamondDiscards => write!(f, "no-diamond-discards"),
+            Rollout::GreedyKill => write!(f, "greedy-kill"),
+            Rollout::MinimalDamageDiscard => write!(f, "minimal-damage-discard"),
+            Rollout::ImmunityAware => write!(f, "immunity-aware"),
+            Rollout::EpsilonGreedy(epsilon) => write!(f, "epsilon-greedy:{}", epsilon),
+        }
+    }
+}
+
+impl FromStr for Rollout {
+    type Err = RegicideError;
+
+    /// Parse the names written by `Display`, where the exploration rate of
+    /// `epsilon-greedy` is optional.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let unknown = || RegicideError::UnknownRollout(s.to_string());
+        let (name, epsilon) = match s.split_once(':') {
+            Some((name, epsilon)) => (name, Some(epsilon.parse::<f64>().map_err(|_| unknown())?)),
+            None => (s, None),
+        };
+        match (name, epsilon) {
+            ("uniform", None) => Ok(Rollout::Uniform),
+            ("no-diamond-discards", None) => Ok(Rollout::NoDiamondDiscards),
+            ("greedy-kill", None) => Ok(Rollout::GreedyKill),
+            ("minimal-damage-discard", None) => Ok(Rollout::MinimalDamageDiscard),
+            ("immunity-aware", None) => Ok(Rollout::ImmunityAware),
+            ("epsilon-greedy", epsilon) => match epsilon.unwrap_or(DEFAULT_EPSILON) {
+                epsilon if (0.0..=1.0).contains(&epsilon) => Ok(Rollout::EpsilonGreedy(epsilon)),
+                _ => Err(unknown()),
+            },
+            _ => Err(unknown()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::card::{CardSuit::*, CardValue::*, FromCardIter, Hand};
+    use crate::game::enemy::Enemy;
+    use rand::SeedableRng;
+
+    const SEED: u64 = 1337;
+
+    #[test]
+    fn greedy_kill_rollout_prefers_exact_kill() {
+        let mut state = State::<2>::new(Some(SEED)).unwrap();
+        let mut enemy = Enemy::new(Card::new(Spades, Jack));
+        enemy.take_damage(14);
+        *state.table_mut().current_enemy_mut().unwrap() = enemy;
+        state.players_mut()[0].hand = Hand::from_card_iter([
+            Card::new(Hearts, Two),
+            Card::new(Hearts, Six),
+            Card::new(Diamonds, Nine),
+        ]);
+
+        let mut rng = StdRng::seed_from_u64(SEED);
+        for _ in 0..10 {
+            let action = Rollout::GreedyKill.choose(&state, state.get_action_space(), &mut rng);
+            assert_eq!(action, Action::Play(Card::new(Hearts, Six)));
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use super::card::CardValue;
+use super::MAX_HAND_SIZE;
+use crate::error::RegicideError;
+#[cfg(feature = "python")]
+use pyo3::exceptions::PyValueError;
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+
+/// Health of a Jack, Queen and King under the official rules.
+pub const OFFICIAL_ENEMY_HEALTH: [i8; 3] = [20, 30, 40];
+/// Attack of a Jack, Queen and King under the official rules.
+pub const OFFICIAL_ENEMY_ATTACK: [u8; 3] = [10, 15, 20];
+
+/// The parameters of the rules of a game. `RuleSet::official` gives the
+/// rules from the rulebook, which can be changed to play difficulty variants
+/// and house rules.
+#[cfg_attr(feature = "python", pyclass(module = "regicide"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RuleSet {
+    /// Number of Jesters shuffled into the Tavern deck, at most 2.
+    #[cfg_attr(feature = "python", pyo3(get, set))]
+    pub jesters: u8,
+    /// Players never draw above this many cards, at most `MAX_HAND_SIZE`.
+    #[cfg_attr(feature = "python", pyo3(get, set))]
+    pub max_hand_size: u8,
+    /// Number of times the hand can be refilled. Only used in solo games.
+    #[cfg_attr(feature = "python", pyo3(get, set))]
+    pub solo_refills: u8,
+    /// Health of a Jack, Queen and King respectively.
+    #[cfg_attr(feature = "python", pyo3(get, set))]
+    pub enemy_health: [i8; 3],
+    /// Attack of a Jack, Queen and King respectively.
+    #[cfg_attr(feature = "python", pyo3(get, set))]
+    pub enemy_attack: [u8; 3],
+    #[cfg_attr(feature = "python", pyo3(get, set))]
+    pub yields_allowed: bool,
+    /// Under the official rules a player cannot yield if every other player
+    /// has just yielded. If set, yielding is always allowed, but the game is
+    /// lost once every player has yielded in a row.
+    #[cfg_attr(feature = "python", pyo3(get, set))]
+    pub lose_on_all_yield: bool,
+    /// The highest total attack value of a combo of Twos to Fives.
+    #[cfg_attr(feature = "python", pyo3(get, set))]
+    pub combo_limit: u16,
+    /// Whether an Ace can be played together with another card.
+    #[cfg_attr(feature = "python", pyo3(get, set))]
+    pub animal_companions: bool,
+}
+
+impl RuleSet {
+    /// The rules from the rulebook for the given number of players.
+    pub fn official(num_players: usize) -> Result<Self, RegicideError> {
+        let (jesters, max_hand_size, solo_refills) = match num_players {
+            1 => (0, 8, 2),
+            2 => (0, 7, 0),
+            3 => (1, 6, 0),
+            4 => (2, 5, 0),
+            _ => return Err(RegicideError::WrongNumberOfPlayers),
+        };
+        Ok(Self {
+            jesters,
+            max_hand_size,
+            solo_refills,
+            enemy_health: OFFICIAL_ENEMY_HEALTH,
+            enemy_attack: OFFICIAL_ENEMY_ATTACK,
+            yields_allowed: true,
+            lose_on_all_yield: false,
+            combo_limit: 10,
+            animal_companions: true,
+        })
+    }
+
+    /// Check that a game can be played with the rules.
+    pub fn validate(&self) -> Result<(), RegicideError> {
+        let invalid = |reason: &str| Err(RegicideError::InvalidRules(reason.to_string()));
+        if self.jesters > 2 {
+            return invalid("at most 2 jesters are supported");
+        }
+        if self.max_hand_size == 0 || self.max_hand_size as usize > MAX_HAND_SIZE {
+            return invalid("max hand size must be between 1 and 8");
+        }
+        if self.enemy_health.iter().any(|&health| health <= 0) {
+            return invalid("enemy health must be positive");
+        }
+        Ok(())
+    }
+
+    /// Health of an enemy with the given value.
+    pub fn enemy_health(&self, value: CardValue) -> i8 {
+        self.enemy_health[royal_index(value)]
+    }
+
+    /// Attack of an enemy with the given value.
+    pub fn enemy_attack(&self, value: CardValue) -> u8 {
+        self.enemy_attack[royal_index(value)]
+    }
+}
+
+/// Index of a royal in the enemy stats, in the order Jack, Queen, King.
+pub(crate) fn royal_index(value: CardValue) -> usize {
+    match value {
+        CardValue::Jack => 0,
+        CardValue::Queen => 1,
+        CardValue::King => 2,
+        _ => panic!("{:?} is not an enemy", value),
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl RuleSet {
+    /// The official rules for `num_players`, which can then be changed.
+    #[new]
+    fn py_new(num_players: usize) -> PyResult<Self> {
+        Self::official(num_players).map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    fn __str__(&self) -> String {
+        format!("{:?}", self)
+    }
+}
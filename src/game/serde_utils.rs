@@ -0,0 +1,63 @@
+//! Serde helpers for fields whose types do not implement `Serialize` and
+//! `Deserialize` themselves. Both are written as plain JSON arrays.
+
+/// For use with `#[serde(with = "...")]` on `ArrayVecCopy` fields.
+pub mod array_vec {
+    use arrayvec::ArrayVecCopy;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S, T, const CAP: usize>(
+        items: &ArrayVecCopy<T, CAP>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Serialize + Copy,
+    {
+        serializer.collect_seq(items.iter())
+    }
+
+    pub fn deserialize<'de, D, T, const CAP: usize>(
+        deserializer: D,
+    ) -> Result<ArrayVecCopy<T, CAP>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de> + Copy,
+    {
+        let items = Vec::<T>::deserialize(deserializer)?;
+        if items.len() > CAP {
+            let expected = format!("at most {} items", CAP);
+            return Err(D::Error::invalid_length(items.len(), &expected.as_str()));
+        }
+        Ok(items.into_iter().collect())
+    }
+}
+
+/// For use with `#[serde(with = "...")]` on arrays with a const generic length.
+pub mod array {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::convert::TryInto;
+
+    pub fn serialize<S, T, const N: usize>(items: &[T; N], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Serialize,
+    {
+        serializer.collect_seq(items.iter())
+    }
+
+    pub fn deserialize<'de, D, T, const N: usize>(deserializer: D) -> Result<[T; N], D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de>,
+    {
+        let items = Vec::<T>::deserialize(deserializer)?;
+        let len = items.len();
+        items.try_into().map_err(|_| {
+            let expected = format!("{} items", N);
+            D::Error::invalid_length(len, &expected.as_str())
+        })
+    }
+}
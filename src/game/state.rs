@@ -1,29 +1,35 @@
 use super::card::{AttackSum, Card, CardSuit, CardValue, FromCardIter, Hand};
 use super::enemy::Enemy;
 use super::player::{Player, PlayerId};
-use super::policy::MyPolicy;
+use super::policy::{MyPolicy, ThreadRngSource};
+use super::rollout::Rollout;
+use super::rules::RuleSet;
 use super::table::Table;
 use crate::error::RegicideError;
-use crate::game::{Action, GameResult, GameStatus};
+use crate::game::{Action, GameResult, GameStatus, SoloOutcome};
 use arrayvec::ArrayVecCopy;
 use itertools::Itertools;
-use rand::prelude::{SliceRandom, StdRng};
+use rand::prelude::StdRng;
 use rand::{RngCore, SeedableRng};
 use std::cmp::Ordering;
 use std::collections::hash_map::DefaultHasher;
 use std::convert::TryInto;
 use std::hash::{Hash, Hasher};
+use std::sync::atomic::{self, AtomicU64};
 
 #[cfg(test)]
 mod tests;
 
 #[derive(Debug, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct State<const N_PLAYERS: usize> {
     table: Table,
+    #[cfg_attr(feature = "serde", serde(with = "crate::game::serde_utils::array"))]
     players: [Player; N_PLAYERS],
     has_turn: PlayerId,
     times_yielded: usize,
-    max_hand_size: u8,
+    #[cfg_attr(feature = "serde", serde(default = "official_rules::<N_PLAYERS>"))]
+    rules: RuleSet,
     action_type: ActionType,
     has_ended: Option<GameResult>,
     level: u8,
@@ -31,8 +37,15 @@ pub struct State<const N_PLAYERS: usize> {
     hand_refills_left: u8,
 }
 
+/// The rules of states serialized before `State` had any.
+#[cfg(feature = "serde")]
+fn official_rules<const N_PLAYERS: usize>() -> RuleSet {
+    RuleSet::official(N_PLAYERS).expect("States only exist for a valid number of players")
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Hash)]
-enum ActionType {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ActionType {
     PlayCards,
     Discard(u8),
     Jester,
@@ -52,18 +65,22 @@ impl<const N_PLAYERS: usize> State<N_PLAYERS> {
         rng
     }
 
+    /// A new game under the official rules.
     pub fn new(seed: Option<u64>) -> Result<Self, RegicideError> {
+        Self::with_rules(seed, RuleSet::official(N_PLAYERS)?)
+    }
+
+    /// A new game under a variant of the rules.
+    pub fn with_rules(seed: Option<u64>, rules: RuleSet) -> Result<Self, RegicideError> {
+        if !(1..=4).contains(&N_PLAYERS) {
+            return Err(RegicideError::WrongNumberOfPlayers);
+        }
+        rules.validate()?;
+
         let mut rng = Self::new_rng(seed);
-        let (n_jesters, max_hand_size, hand_refills_left) = match N_PLAYERS {
-            1 => (0, 8, 2),
-            2 => (0, 7, 0),
-            3 => (1, 6, 0),
-            4 => (2, 5, 0),
-            _ => return Err(RegicideError::WrongNumberOfPlayers),
-        };
-        let mut table = Table::new(n_jesters, &mut rng);
+        let mut table = Table::new(&rules, &mut rng);
         let players = (0..N_PLAYERS)
-            .map(|id| Player::new(id, table.draw_cards(max_hand_size)))
+            .map(|id| Player::new(id, table.draw_cards(rules.max_hand_size.into())))
             .collect_vec()
             .try_into()
             .unwrap();
@@ -73,12 +90,12 @@ impl<const N_PLAYERS: usize> State<N_PLAYERS> {
             players,
             has_turn: PlayerId(0),
             times_yielded: 0,
-            max_hand_size: max_hand_size as u8,
+            rules,
             action_type: ActionType::PlayCards,
             has_ended: None,
             level: 0,
             rng_seed: rng.next_u64(),
-            hand_refills_left,
+            hand_refills_left: rules.solo_refills,
         })
     }
 
@@ -90,19 +107,77 @@ impl<const N_PLAYERS: usize> State<N_PLAYERS> {
         self.current_player().hand
     }
 
+    pub fn table(&self) -> &Table {
+        &self.table
+    }
+
+    pub fn action_type(&self) -> ActionType {
+        self.action_type
+    }
+
+    pub fn times_yielded(&self) -> usize {
+        self.times_yielded
+    }
+
+    pub fn players(&self) -> &[Player; N_PLAYERS] {
+        &self.players
+    }
+
+    pub fn hand_refills_left(&self) -> u8 {
+        self.hand_refills_left
+    }
+
+    /// The rating a solo victory would get with the refills used so far.
+    /// `None` in games with more than one player.
+    pub fn solo_outcome(&self) -> Option<SoloOutcome> {
+        match N_PLAYERS {
+            1 => Some(SoloOutcome::from_refills_used(
+                self.rules
+                    .solo_refills
+                    .saturating_sub(self.hand_refills_left),
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
     pub fn take_action(&self, action: &Action) -> GameStatus<N_PLAYERS> {
         let next_state = *self;
         Self::apply_action(next_state, action)
     }
 
+    /// Take an action in place. Unlike `take_action`, the state after an action
+    /// that ends the game is kept, with `result()` set to the result of the game.
+    pub fn take_action_mut(&mut self, action: &Action) -> Option<GameResult> {
+        let result = self.advance(action);
+        self.has_ended = result;
+        result
+    }
+
+    /// How the game ended, if it ended through `take_action_mut`.
+    pub fn result(&self) -> Option<GameResult> {
+        self.has_ended
+    }
+
     fn apply_action(mut self, action: &Action) -> GameStatus<N_PLAYERS> {
+        match self.advance(action) {
+            None => GameStatus::InProgress(self),
+            Some(result) => GameStatus::HasEnded(result),
+        }
+    }
+
+    /// Apply an action to the state, returning the result if it ends the game.
+    fn advance(&mut self, action: &Action) -> Option<GameResult> {
         self.times_yielded = match action {
             Action::Discard(_) | Action::RefillHand => self.times_yielded,
             Action::Yield => self.times_yielded + 1,
             _ => 0,
         };
 
-        match action {
+        let result = match action {
             Action::Play(c) => self.play_cards(Hand::from_card_iter([*c])),
             Action::AnimalCombo(c1, c2) => self.play_cards(Hand::from_card_iter([*c1, *c2])),
             Action::Combo(cards) => {
@@ -120,32 +195,43 @@ impl<const N_PLAYERS: usize> State<N_PLAYERS> {
 
                 self.action_type = ActionType::PlayCards;
                 self.next_player();
-                GameStatus::InProgress(self)
+                None
             }
             Action::Yield => {
                 if self.times_yielded < self.players.len() {
                     self.play_cards(Hand::new())
                 } else {
-                    // All players cannot yield consequtively
-                    GameStatus::HasEnded(GameResult::Lost(self.reward()))
+                    // All players cannot yield consequtively. Only reachable
+                    // with `RuleSet::lose_on_all_yield`, since yielding is
+                    // otherwise not allowed for the last player.
+                    Some(GameResult::Lost(self.reward()))
                 }
             }
             Action::ChangePlayer(id) => {
                 self.has_turn = *id;
                 self.action_type = ActionType::PlayCards;
-                GameStatus::InProgress(self)
+                None
             }
             Action::RefillHand => {
                 self.hand_refills_left -= 1;
                 let hand = self.current_player_mut().hand;
                 self.table.discard_cards(hand);
-                self.current_player_mut().hand = self.table.draw_cards(self.max_hand_size.into());
-                GameStatus::InProgress(self)
+                self.current_player_mut().hand =
+                    self.table.draw_cards(self.rules.max_hand_size.into());
+                None
             }
+        };
+
+        match result {
+            // A player who can neither play a card nor yield has lost
+            None if self.current_hand().is_empty() && self.get_action_space().is_empty() => {
+                Some(GameResult::Lost(self.reward()))
+            }
+            result => result,
         }
     }
 
-    fn play_cards<'a>(mut self, cards: Hand) -> GameStatus<N_PLAYERS> {
+    fn play_cards(&mut self, cards: Hand) -> Option<GameResult> {
         use super::card::CardSuit::*;
 
         // Step 1: Play a card from hand to attack the enemy
@@ -221,7 +307,7 @@ impl<const N_PLAYERS: usize> State<N_PLAYERS> {
                             for offset in 0..self.players.len() as u16 {
                                 let index = ((i + offset) % self.players.len() as u16) as usize;
                                 let player = self.players.get_mut(index).unwrap();
-                                if (player.hand.len()) < self.max_hand_size as usize {
+                                if (player.hand.len()) < self.rules.max_hand_size as usize {
                                     if let Some(card) = self.table.draw_card() {
                                         player.hand.push(card);
                                     }
@@ -254,14 +340,14 @@ impl<const N_PLAYERS: usize> State<N_PLAYERS> {
                         self.table.discard_attack_cards();
                         self.table.next_enemy();
                         self.level += 1;
-                        GameStatus::InProgress(self)
+                        Option::None
                     }
                     Ordering::Equal => {
                         self.table.add_to_top_of_tavern_deck(enemy_card);
                         self.table.discard_attack_cards();
                         self.table.next_enemy();
                         self.level += 1;
-                        GameStatus::InProgress(self)
+                        Option::None
                     }
                     Ordering::Greater => {
                         // Step 4: Suffer damage from the enemy by discarding cards
@@ -269,9 +355,9 @@ impl<const N_PLAYERS: usize> State<N_PLAYERS> {
                         let player_health = self.current_player().total_hand_value();
 
                         if self.action_type == ActionType::Jester {
-                            GameStatus::InProgress(self)
+                            Option::None
                         } else if enemy_attack as u16 > player_health {
-                            GameStatus::HasEnded(GameResult::Lost(self.reward()))
+                            Some(GameResult::Lost(self.reward()))
                         } else {
                             // Player only needs to discard if they take damage
                             self.action_type = match enemy_attack {
@@ -281,12 +367,12 @@ impl<const N_PLAYERS: usize> State<N_PLAYERS> {
                                 }
                                 _ => ActionType::Discard(enemy_attack),
                             };
-                            GameStatus::InProgress(self)
+                            Option::None
                         }
                     }
                 }
             }
-            Option::None => GameStatus::HasEnded(GameResult::Won),
+            Option::None => Some(GameResult::Won(self.solo_outcome())),
         }
     }
 
@@ -311,6 +397,9 @@ impl<const N_PLAYERS: usize> State<N_PLAYERS> {
     }
 
     pub fn get_action_space(&self) -> Vec<Action> {
+        if self.has_ended.is_some() {
+            return vec![];
+        }
         let player = self.current_player();
 
         let mut actions = match self.action_type {
@@ -366,7 +455,7 @@ impl<const N_PLAYERS: usize> State<N_PLAYERS> {
         let animal_combos = player
             .hand
             .iter()
-            .filter(|card1| card1.value == Ace)
+            .filter(|card1| card1.value == Ace && self.rules.animal_companions)
             .flat_map(|card1| {
                 player
                     .hand
@@ -398,7 +487,7 @@ impl<const N_PLAYERS: usize> State<N_PLAYERS> {
                         .map(|c| **c)
                         .collect::<Hand>()
                         .attack_sum()
-                        <= 10
+                        <= self.rules.combo_limit
                 })
                 .map(|combo| {
                     Action::Combo(ArrayVecCopy::<Card, 4>::from_card_iter(
@@ -408,7 +497,11 @@ impl<const N_PLAYERS: usize> State<N_PLAYERS> {
             actions.extend(combos);
         }
 
-        actions.push(Action::Yield);
+        // A player may not yield if every other player has just yielded
+        let can_yield = self.rules.lose_on_all_yield || self.times_yielded + 1 < N_PLAYERS;
+        if self.rules.yields_allowed && can_yield {
+            actions.push(Action::Yield);
+        }
         actions
     }
 
@@ -420,10 +513,39 @@ impl<const N_PLAYERS: usize> State<N_PLAYERS> {
             .filter(|player| player.id() != self.current_player().id())
             .map(|player| &mut player.hand)
             .collect_vec();
-        new_state.table.permute(&mut player_hands[..], rng);
+        new_state
+            .table
+            .permute(&mut player_hands[..], &self.rules, rng);
+
+        new_state
+    }
 
+    /// Sample a state that looks the same to the player who has the turn.
+    /// Unlike `random_permutation`, the seed used for future shuffles is also resampled.
+    pub fn determinize(&self, rng: &mut StdRng) -> State<N_PLAYERS> {
+        let mut new_state = self.random_permutation(rng);
+        new_state.rng_seed = rng.next_u64();
         new_state
     }
+
+    /// Play actions chosen by `policy` until the game ends.
+    pub fn rollout(&self, policy: &Rollout, rng: &mut StdRng) -> GameResult {
+        let mut node = *self;
+        loop {
+            if let Some(res) = node.has_ended {
+                return res;
+            }
+            let moves = node.available_moves();
+            if moves.is_empty() {
+                return GameResult::Lost(node.reward());
+            }
+            let action = policy.choose(&node, moves, rng);
+            match node.apply_action(&action) {
+                GameStatus::InProgress(new_state) => node = new_state,
+                GameStatus::HasEnded(res) => return res,
+            }
+        }
+    }
 }
 
 use mcts::{Evaluator, GameState, MoveEvaluation, SearchHandle, MCTS};
@@ -451,85 +573,86 @@ impl<const N_PLAYERS: usize> GameState for State<N_PLAYERS> {
     }
 }
 
+/// Setters for arranging positions in the tests of other modules.
+#[cfg(test)]
+impl<const N_PLAYERS: usize> State<N_PLAYERS> {
+    pub(crate) fn table_mut(&mut self) -> &mut Table {
+        &mut self.table
+    }
+
+    pub(crate) fn players_mut(&mut self) -> &mut [Player; N_PLAYERS] {
+        &mut self.players
+    }
+
+    pub(crate) fn set_has_turn(&mut self, player: PlayerId) {
+        self.has_turn = player;
+    }
+
+    pub(crate) fn set_rng_seed(&mut self, seed: u64) {
+        self.rng_seed = seed;
+    }
+}
+
 impl<const N_PLAYERS: usize> TranspositionHash for State<N_PLAYERS> {
+    /// Equal for states that only differ in the seed used for future shuffles
+    /// or in the order of cards in hands, the discard pile and the attack cards,
+    /// so that these positions share statistics in the search tree.
     fn hash(&self) -> u64 {
+        let sorted = |cards: &[Card]| cards.iter().map(Card::index).sorted().collect_vec();
+
         let mut hasher = DefaultHasher::new();
-        Hash::hash(&self, &mut hasher);
+        for player in self.players.iter() {
+            sorted(&player.hand).hash(&mut hasher);
+        }
+        self.table.castle_deck().hash(&mut hasher);
+        self.table.tavern_deck().hash(&mut hasher);
+        sorted(self.table.discard_pile()).hash(&mut hasher);
+        sorted(self.table.attack_cards()).hash(&mut hasher);
+        self.has_turn.hash(&mut hasher);
+        self.times_yielded.hash(&mut hasher);
+        self.rules.hash(&mut hasher);
+        self.action_type.hash(&mut hasher);
+        self.has_ended.hash(&mut hasher);
+        self.level.hash(&mut hasher);
+        self.hand_refills_left.hash(&mut hasher);
         hasher.finish()
     }
 }
 
-pub struct MyEvaluator<const N_PLAYERS: usize>;
-
-fn prune_bad_moves(all_moves: Vec<Action>) -> Vec<Action> {
-    let filters = vec![
-        // Never discard any Diamonds
-        |m: &&Action| match m {
-            Action::Discard(cards) => cards.iter().all(|card| match card {
-                Card {
-                    suit: CardSuit::Diamonds,
-                    ..
-                } => false,
-                _ => true,
-            }),
-            _ => true,
-        },
-    ];
-    let mut moves_iter = all_moves;
-    for f in filters {
-        let foo = moves_iter.iter().filter(f).copied().collect_vec();
-        if foo.len() > 0 {
-            moves_iter = foo;
-        }
+pub struct MyEvaluator<const N_PLAYERS: usize> {
+    rollout: Rollout,
+}
+
+impl<const N_PLAYERS: usize> MyEvaluator<N_PLAYERS> {
+    /// Evaluates new positions with a rollout chosen by `rollout`.
+    pub fn new(rollout: Rollout) -> Self {
+        Self { rollout }
     }
-    moves_iter
 }
 
-impl<const N_PLAYERS: usize, const HEURISTICS: bool> Evaluator<MyMCTS<N_PLAYERS, HEURISTICS>>
-    for MyEvaluator<N_PLAYERS>
-{
+impl<const N_PLAYERS: usize> Evaluator<MyMCTS<N_PLAYERS>> for MyEvaluator<N_PLAYERS> {
     type StateEvaluation = GameResult;
 
-    // Random default policy
+    // Rollout with the policy of the evaluator
     fn evaluate_new_state(
         &self,
         state: &State<N_PLAYERS>,
         moves: &Vec<Action>,
-        _handle: Option<SearchHandle<MyMCTS<N_PLAYERS, HEURISTICS>>>,
-    ) -> (
-        Vec<MoveEvaluation<MyMCTS<N_PLAYERS, HEURISTICS>>>,
-        GameResult,
-    ) {
+        mut handle: Option<SearchHandle<MyMCTS<N_PLAYERS>>>,
+    ) -> (Vec<MoveEvaluation<MyMCTS<N_PLAYERS>>>, GameResult) {
         let mut node = *state;
-        let mut rng = rand::rngs::StdRng::from_rng(rand::thread_rng()).unwrap();
-        node = node.random_permutation(&mut rng);
-        let result;
-        loop {
-            let mut moves = node.available_moves();
-            if HEURISTICS {
-                moves = prune_bad_moves(moves);
+        // There is no handle when the root is created. Its evaluation is never
+        // used, since `evaluate_existing_state` runs a new rollout.
+        let mut root_rng;
+        let rng = match handle.as_mut() {
+            Some(handle) => search_rng(handle),
+            None => {
+                root_rng = StdRng::from_rng(rand::thread_rng()).unwrap();
+                &mut root_rng
             }
-            match moves.choose(&mut rng) {
-                Some(random_action) => match node.apply_action(random_action) {
-                    GameStatus::InProgress(new_state) => {
-                        if let Some(res) = new_state.has_ended {
-                            result = res;
-                            break;
-                        } else {
-                            node = new_state;
-                        }
-                    }
-                    GameStatus::HasEnded(res) => {
-                        result = res;
-                        break;
-                    }
-                },
-                None => {
-                    result = GameResult::Lost(node.reward());
-                    break;
-                }
-            }
-        }
+        };
+        node = node.random_permutation(rng);
+        let result = node.rollout(&self.rollout, rng);
         (vec![(); moves.len()], result)
     }
 
@@ -537,7 +660,7 @@ impl<const N_PLAYERS: usize, const HEURISTICS: bool> Evaluator<MyMCTS<N_PLAYERS,
         &self,
         state: &State<N_PLAYERS>,
         _evaln: &GameResult,
-        handle: SearchHandle<MyMCTS<N_PLAYERS, HEURISTICS>>,
+        handle: SearchHandle<MyMCTS<N_PLAYERS>>,
     ) -> GameResult {
         self.evaluate_new_state(state, &state.available_moves(), Some(handle))
             .1
@@ -546,17 +669,55 @@ impl<const N_PLAYERS: usize, const HEURISTICS: bool> Evaluator<MyMCTS<N_PLAYERS,
 
     fn interpret_evaluation_for_player(&self, evaln: &GameResult, _player: &Player) -> i64 {
         match evaln {
-            GameResult::Won => GameResult::max_score().into(),
+            GameResult::Won(_) => GameResult::max_score().into(),
             GameResult::Lost(reward) => (*reward).into(),
         }
     }
 }
 
-use mcts::transposition_table::{TranspositionHash, TranspositionTable};
+use mcts::transposition_table::{ApproxTable, TranspositionHash, TranspositionTable};
 use mcts::CycleBehaviour;
 
 #[derive(Default)]
-pub struct MyMCTS<const N_PLAYERS: usize, const HEURISTICS: bool>;
+pub struct MyMCTS<const N_PLAYERS: usize> {
+    seed: Option<u64>,
+    seeded_threads: AtomicU64,
+}
+
+impl<const N_PLAYERS: usize> MyMCTS<N_PLAYERS> {
+    /// With a seed, every search thread gets its own deterministically seeded
+    /// random number generator. The search is then reproducible when using a
+    /// single thread, since the interleaving of threads is not deterministic.
+    pub fn new(seed: Option<u64>) -> Self {
+        Self {
+            seed,
+            seeded_threads: AtomicU64::new(0),
+        }
+    }
+}
+
+impl<const N_PLAYERS: usize> ThreadRngSource for MyMCTS<N_PLAYERS> {
+    fn new_thread_rng(&self) -> StdRng {
+        match self.seed {
+            Some(seed) => {
+                let thread = self.seeded_threads.fetch_add(1, atomic::Ordering::Relaxed);
+                StdRng::seed_from_u64(seed.wrapping_add(thread))
+            }
+            None => StdRng::from_rng(rand::thread_rng()).unwrap(),
+        }
+    }
+}
+
+/// The random number generator used for rollouts by the thread of `handle`.
+fn search_rng<'a, const N_PLAYERS: usize>(
+    handle: &'a mut SearchHandle<MyMCTS<N_PLAYERS>>,
+) -> &'a mut StdRng {
+    if handle.thread_data().extra_data.is_none() {
+        let rng = handle.mcts().new_thread_rng();
+        handle.thread_data().extra_data = Some(rng);
+    }
+    handle.thread_data().extra_data.as_mut().unwrap()
+}
 
 pub struct EmptyTable;
 
@@ -579,13 +740,13 @@ unsafe impl<Spec: MCTS> TranspositionTable<Spec> for EmptyTable {
     }
 }
 
-impl<const N_PLAYERS: usize, const HEURISTICS: bool> MCTS for MyMCTS<N_PLAYERS, HEURISTICS> {
+impl<const N_PLAYERS: usize> MCTS for MyMCTS<N_PLAYERS> {
     type State = State<N_PLAYERS>;
     type Eval = MyEvaluator<N_PLAYERS>;
     type NodeData = ();
-    type ExtraThreadData = ();
+    type ExtraThreadData = Option<StdRng>;
     type TreePolicy = MyPolicy;
-    type TranspositionTable = EmptyTable;
+    type TranspositionTable = ApproxTable<Self>;
 
     fn max_playout_length(&self) -> usize {
         1_000
@@ -1,9 +1,10 @@
 
-use super::State;
+use super::{ActionType, State};
 use crate::game::{
     card::{CardSuit::*, CardValue::*, FromCardIter},
     enemy::Enemy,
-    Action, Card, GameStatus, Hand,
+    player::PlayerId,
+    Action, Card, GameResult, GameStatus, Hand,
 };
 
 #[test]
@@ -12,16 +13,10 @@ fn jester_removes_immunity() {
     *state.table.current_enemy_mut().unwrap() = Enemy::new(Card::new(Clubs, Queen));
 
     assert_eq!(state.current_enemy().unwrap().health(), 30);
-    state = match state.take_action(&Action::Play(Card::new(None, Jester))) {
-        GameStatus::InProgress(state) => state,
-        _ => panic!("Game should not have ended"),
-    };
+    state = in_progress(state.take_action(&Action::Play(Card::new(None, Jester))));
     assert_eq!(state.current_enemy().unwrap().health(), 30);
 
-    state = match state.take_action(&Action::Play(Card::new(Clubs, Two))) {
-        GameStatus::InProgress(state) => state,
-        _ => panic!("Game should not have ended"),
-    };
+    state = in_progress(state.take_action(&Action::Play(Card::new(Clubs, Two))));
     assert_eq!(state.current_enemy().unwrap().health(), 26);
 }
 
@@ -31,10 +26,7 @@ fn enemies_are_immune() {
     *state.table.current_enemy_mut().unwrap() = Enemy::new(Card::new(Clubs, Queen));
 
     assert_eq!(state.current_enemy().unwrap().health(), 30);
-    state = match state.take_action(&Action::Play(Card::new(Clubs, Two))) {
-        GameStatus::InProgress(state) => state,
-        _ => panic!("Game should not have ended"),
-    };
+    state = in_progress(state.take_action(&Action::Play(Card::new(Clubs, Two))));
     assert_eq!(state.current_enemy().unwrap().health(), 28);
 }
 
@@ -77,6 +69,14 @@ fn combo_count(actions: &Vec<Action>, variant: &str, combo_len: usize) -> usize
         .count()
 }
 
+/// The state after an action that should not end the game.
+fn in_progress<const N_PLAYERS: usize>(status: GameStatus<N_PLAYERS>) -> State<N_PLAYERS> {
+    match status {
+        GameStatus::InProgress(state) => state,
+        _ => panic!("Game should not have ended"),
+    }
+}
+
 #[test]
 fn two_card_combos() {
     let mut state = crate::game::state::State::<1>::new(Some(SEED)).unwrap();
@@ -112,3 +112,237 @@ fn four_card_combos() {
     assert_eq!(combo_count(&actions, "combo", 3), 4);
     assert_eq!(combo_count(&actions, "combo", 4), 1);
 }
+
+#[test]
+fn encoding_has_fixed_size() {
+    let mut state = State::<3>::new(Some(SEED)).unwrap();
+    state.players[0].hand = hand!(
+        (Diamonds, Two),
+        (Clubs, Queen),
+        (None, Jester),
+        (None, Jester)
+    );
+    let encoding = state.encode();
+    assert_eq!(encoding.len(), crate::game::encoding::OBSERVATION_SIZE);
+    assert_eq!(encoding[..54].iter().sum::<f32>(), 4.0);
+}
+
+#[test]
+fn action_indices_are_unique_and_invertible() {
+    let mut state = State::<1>::new(Some(SEED)).unwrap();
+    state.players[0].hand = hand!(
+        (Diamonds, Ace),
+        (Hearts, Ace),
+        (Spades, Two),
+        (Clubs, Two),
+        (Hearts, Two),
+        (Spades, King),
+    );
+    let actions = state.get_action_space();
+    let indices = actions
+        .iter()
+        .map(|action| state.action_index(action).unwrap())
+        .collect::<std::collections::HashSet<_>>();
+    assert_eq!(indices.len(), actions.len());
+
+    let mask = state.action_mask();
+    assert_eq!(mask.iter().filter(|&&legal| legal).count(), actions.len());
+    for action in actions {
+        let index = state.action_index(&action).unwrap();
+        assert_eq!(state.action_from_index(index), Some(action));
+    }
+}
+
+#[test]
+fn observation_only_has_actions_for_player_with_turn() {
+    let state = State::<3>::new(Some(SEED)).unwrap();
+    let own = state.observation(PlayerId(0));
+    let other = state.observation(PlayerId(1));
+    assert_eq!(own.action_space(), &state.get_action_space()[..]);
+    assert!(other.action_space().is_empty());
+    assert_eq!(other.hand(), state.players[1].hand);
+    assert_eq!(own.hand_sizes(), &[6, 6, 6]);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn state_survives_json_round_trip() {
+    let state = State::<3>::new(Some(SEED)).unwrap();
+    let action = state.get_action_space()[0];
+    let state = in_progress(state.take_action(&action));
+
+    let json = serde_json::to_string(&state).unwrap();
+    let restored: State<3> = serde_json::from_str(&json).unwrap();
+    assert_eq!(format!("{:?}", restored), format!("{:?}", state));
+    assert!(serde_json::from_str::<State<2>>(&json).is_err());
+
+    let action_json = serde_json::to_string(&action).unwrap();
+    assert_eq!(
+        serde_json::from_str::<Action>(&action_json).unwrap(),
+        action
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn state_without_rules_loads_with_official_rules() {
+    use crate::game::rules::RuleSet;
+
+    // States serialized before rules were added have no `rules` field
+    let state = State::<3>::new(Some(SEED)).unwrap();
+    let mut json = serde_json::to_value(&state).unwrap();
+    json.as_object_mut().unwrap().remove("rules").unwrap();
+
+    let restored: State<3> = serde_json::from_value(json).unwrap();
+    assert_eq!(*restored.rules(), RuleSet::official(3).unwrap());
+    assert_eq!(format!("{:?}", restored), format!("{:?}", state));
+}
+
+#[test]
+fn record_replays_to_same_state() {
+    use crate::game::record::{GameRecord, RecordStep};
+    use crate::game::rules::RuleSet;
+
+    let mut record = GameRecord::new(2, SEED, RuleSet::official(2).unwrap());
+    let mut state = State::<2>::new(Some(SEED)).unwrap();
+    for _ in 0..5 {
+        let action = state.get_action_space()[0];
+        let player = state.has_turn();
+        let hands_before = state.players().map(|p| p.hand);
+        state = in_progress(state.take_action(&action));
+        let hands_after = state.players().map(|p| p.hand);
+        record.push(RecordStep::new(
+            player,
+            action,
+            state.current_enemy().copied(),
+            &hands_before,
+            Some(&hands_after[..]),
+        ));
+    }
+
+    let replayed = record.replay::<2>(5).unwrap();
+    assert_eq!(format!("{:?}", replayed), format!("{:?}", state));
+    assert!(record.replay::<2>(6).is_err());
+    assert!(record.replay::<3>(5).is_err());
+}
+
+#[test]
+fn notation_round_trips() {
+    let state = State::<2>::new(Some(SEED)).unwrap();
+    for action in state.get_action_space() {
+        assert_eq!(state.parse_action(&action.to_string()).unwrap(), action);
+    }
+
+    assert_eq!("JK".parse::<Card>().unwrap(), Card::new(None, Jester));
+    assert_eq!("10h".parse::<Card>().unwrap(), Card::new(Hearts, Ten));
+    assert_eq!(
+        "9C+AS".parse::<Action>().unwrap(),
+        Action::AnimalCombo(Card::new(Spades, Ace), Card::new(Clubs, Nine))
+    );
+    assert_eq!(
+        "discard[3D,5S]".parse::<Action>().unwrap(),
+        Action::Discard(hand![(Diamonds, Three), (Spades, Five)])
+    );
+    assert!("1S".parse::<Card>().is_err());
+}
+
+#[test]
+fn rule_set_changes_game() {
+    use crate::game::rules::RuleSet;
+
+    let mut rules = RuleSet::official(2).unwrap();
+    rules.max_hand_size = 4;
+    rules.enemy_health = [10, 20, 30];
+    rules.yields_allowed = false;
+    rules.animal_companions = false;
+    let state = State::<2>::with_rules(Some(SEED), rules).unwrap();
+
+    assert!(state.players().iter().all(|p| p.hand.len() == 4));
+    assert_eq!(state.current_enemy().unwrap().health(), 10);
+    assert!(state
+        .get_action_space()
+        .iter()
+        .all(|action| matches!(action, Action::Play(_) | Action::Combo(_))));
+
+    rules.max_hand_size = 9;
+    assert!(State::<2>::with_rules(Some(SEED), rules).is_err());
+}
+
+#[test]
+fn last_player_cannot_yield() {
+    use crate::game::rules::RuleSet;
+
+    let mut state = State::<2>::new(Some(SEED)).unwrap();
+    state = in_progress(state.take_action(&Action::Yield));
+    if let ActionType::Discard(_) = state.action_type() {
+        let discard = state.get_action_space()[0];
+        state = in_progress(state.take_action(&discard));
+    }
+    assert_eq!(state.times_yielded(), 1);
+    assert!(!state.get_action_space().contains(&Action::Yield));
+    assert!(!State::<1>::new(Some(SEED))
+        .unwrap()
+        .get_action_space()
+        .contains(&Action::Yield));
+
+    let mut rules = RuleSet::official(2).unwrap();
+    rules.lose_on_all_yield = true;
+    state.rules = rules;
+    assert!(state.get_action_space().contains(&Action::Yield));
+    assert!(matches!(
+        state.take_action(&Action::Yield),
+        GameStatus::HasEnded(GameResult::Lost(_))
+    ));
+}
+
+#[test]
+fn solo_outcome_depends_on_refills_used() {
+    use crate::game::SoloOutcome;
+
+    let state = State::<1>::new(Some(SEED)).unwrap();
+    assert_eq!(state.solo_outcome(), Some(SoloOutcome::Gold));
+    let state = in_progress(state.take_action(&Action::RefillHand));
+    assert_eq!(state.solo_outcome(), Some(SoloOutcome::Silver));
+    assert_eq!(State::<2>::new(Some(SEED)).unwrap().solo_outcome(), None);
+}
+
+#[test]
+fn take_action_mut_keeps_final_state() {
+    let mut state = State::<2>::new(Some(SEED)).unwrap();
+    let result = loop {
+        assert_eq!(state.result(), None);
+        let action = state.get_action_space()[0];
+        if let Some(result) = state.take_action_mut(&action) {
+            break result;
+        }
+    };
+    assert_eq!(state.result(), Some(result));
+    assert_eq!(state.reward(), result.reward());
+    assert!(state.get_action_space().is_empty());
+}
+
+#[test]
+fn determinize_keeps_what_player_with_turn_knows() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let mut state = State::<3>::new(Some(SEED)).unwrap();
+    // A defeated enemy on top of the Tavern deck, a discard pile and a damaged enemy
+    let defeated = *state.current_enemy().unwrap().card();
+    state.table.next_enemy();
+    let discarded = state.table.draw_cards(4);
+    state.table.discard_cards(discarded);
+    state.table.add_to_top_of_tavern_deck(defeated);
+    state.table.current_enemy_mut().unwrap().take_damage(5);
+
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let mut other_hands_changed = false;
+    for _ in 0..10 {
+        let sample = state.determinize(&mut rng);
+        assert_eq!(sample.current_enemy(), state.current_enemy());
+        assert_eq!(sample.table.discard_pile(), state.table.discard_pile());
+        assert_eq!(sample.table.tavern_deck().last(), Some(&defeated));
+        assert_eq!(sample.players[0].hand, state.players[0].hand);
+        other_hands_changed |= sample.players[1].hand != state.players[1].hand;
+    }
+    assert!(other_hands_changed);
+}
@@ -1,23 +1,28 @@
 use super::card::{Card, CardSuit, CardValue, CardVec, Hand};
 use super::enemy::Enemy;
-use crate::game::card::FromCardIter;
+use super::rules::RuleSet;
 use arrayvec::ArrayVecCopy;
 use itertools::Itertools;
 use rand::prelude::SliceRandom;
 use rand::rngs::StdRng;
 
 #[derive(Debug, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Table {
+    #[cfg_attr(feature = "serde", serde(with = "crate::game::serde_utils::array_vec"))]
     castle_deck: ArrayVecCopy<Enemy, 12>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::game::serde_utils::array_vec"))]
     tavern_deck: CardVec,
+    #[cfg_attr(feature = "serde", serde(with = "crate::game::serde_utils::array_vec"))]
     discard_pile: CardVec,
+    #[cfg_attr(feature = "serde", serde(with = "crate::game::serde_utils::array_vec"))]
     attack_cards: CardVec,
 }
 
 impl Table {
-    pub fn new(n_jesters: usize, rng: &mut StdRng) -> Self {
-        let castle_deck = Self::new_castle_deck(rng);
-        let tavern_deck = Self::new_tavern_deck(rng, n_jesters);
+    pub fn new(rules: &RuleSet, rng: &mut StdRng) -> Self {
+        let castle_deck = Self::new_castle_deck(rng, rules);
+        let tavern_deck = Self::new_tavern_deck(rng, rules.jesters as usize);
         let attack_cards = CardVec::new();
         let discard_pile = CardVec::new();
 
@@ -29,11 +34,11 @@ impl Table {
         }
     }
 
-    fn new_castle_deck(rng: &mut StdRng) -> ArrayVecCopy<Enemy, 12> {
+    fn new_castle_deck(rng: &mut StdRng, rules: &RuleSet) -> ArrayVecCopy<Enemy, 12> {
         IntoIterator::into_iter(CardValue::royals())
             .flat_map(|value| {
                 let mut level = IntoIterator::into_iter(CardSuit::all())
-                    .map(|suit| Enemy::new(Card::new(suit, value)))
+                    .map(|suit| Enemy::with_rules(Card::new(suit, value), rules))
                     .collect_vec();
                 level.shuffle(rng);
                 level
@@ -87,6 +92,19 @@ impl Table {
         &self.attack_cards
     }
 
+    /// Remaining enemies, with the current enemy last.
+    pub fn castle_deck(&self) -> &ArrayVecCopy<Enemy, 12> {
+        &self.castle_deck
+    }
+
+    pub fn tavern_deck(&self) -> &CardVec {
+        &self.tavern_deck
+    }
+
+    pub fn discard_pile(&self) -> &CardVec {
+        &self.discard_pile
+    }
+
     /// Place all cards played by players against the enemy in the discard pile.
     pub fn discard_attack_cards(&mut self) {
         self.discard_pile.extend(self.attack_cards.drain(..));
@@ -119,32 +137,51 @@ impl Table {
         self.castle_deck.pop();
     }
 
-    pub fn permute<'a>(&mut self, player_hands: &'a mut [&'a mut Hand], rng: &mut StdRng) {
-        // Shuffle castle deck
-        self.castle_deck = Self::new_castle_deck(rng)
-            .iter()
-            .filter(|enemy| self.castle_deck.contains(enemy))
-            .copied()
-            .collect();
+    /// Shuffle the cards that are hidden from the player who has the turn:
+    /// the enemies below the current enemy, within their level, and the cards
+    /// of `player_hands` and the Tavern deck. Defeated enemies stay where
+    /// they are, since where they went is public.
+    pub fn permute<'a>(
+        &mut self,
+        player_hands: &'a mut [&'a mut Hand],
+        rules: &RuleSet,
+        rng: &mut StdRng,
+    ) {
+        // Shuffle castle deck below the current enemy
+        if let Some(current_enemy) = self.castle_deck.pop() {
+            let remaining = self
+                .castle_deck
+                .iter()
+                .map(|enemy| *enemy.card())
+                .collect_vec();
+            self.castle_deck = Self::new_castle_deck(rng, rules)
+                .iter()
+                .filter(|enemy| remaining.contains(enemy.card()))
+                .copied()
+                .collect();
+            self.castle_deck.push(current_enemy);
+        }
 
-        // Combine all potentially unknown cards into a single pile:
-        // hands + discard pile + tavern deck
-        let discard_pile = self.discard_pile.clone();
-        let player_cards = player_hands
-            .into_iter()
+        // Shuffle the hidden cards among the places they can be in
+        let is_hidden = |card: &Card| !CardValue::royals().contains(&card.value);
+        let mut hidden_cards = player_hands
+            .iter()
             .flat_map(|hand| hand.iter())
+            .chain(self.tavern_deck.iter())
             .copied()
-            .collect();
-        let tavern_deck = self.tavern_deck.iter().map(|c| *c).collect();
-        let mut combined_cards =
-            CardVec::from_card_iter([discard_pile, player_cards, tavern_deck].concat());
-        combined_cards.shuffle(rng);
-
-        // Distribute cards back out
-        for hand in player_hands {
-            **hand = combined_cards.drain(..hand.len()).collect();
+            .filter(|card| is_hidden(card))
+            .collect::<CardVec>();
+        hidden_cards.shuffle(rng);
+
+        let mut hidden_cards = hidden_cards.iter().copied();
+        for card in player_hands
+            .iter_mut()
+            .flat_map(|hand| hand.iter_mut())
+            .chain(self.tavern_deck.iter_mut())
+        {
+            if is_hidden(card) {
+                *card = hidden_cards.next().unwrap();
+            }
         }
-        self.tavern_deck = combined_cards.drain(..self.tavern_deck.len()).collect();
-        self.discard_pile = combined_cards;
     }
 }
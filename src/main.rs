@@ -0,0 +1,261 @@
+//! Play or simulate games of Regicide from the command line.
+
+use regicide::error::RegicideError;
+use regicide::game::rollout::Rollout;
+use regicide::game::rules::RuleSet;
+use regicide::game::state::State;
+use regicide::game::{GameResult, GameStatus, SoloOutcome};
+use regicide::players::{
+    input_player::InputPlayer, mcts_player::MCTSPlayer, random_player::RandomPlayer, Play,
+};
+use regicide::self_play::{self_play_game, write_npy, SelfPlaySample};
+use std::error::Error;
+use std::fs::File;
+use std::io::BufWriter;
+use std::process::exit;
+use std::str::FromStr;
+
+const USAGE: &str = "\
+Usage:
+    regicide-cli play [--players N] [--seed S]
+    regicide-cli simulate [--player random|mcts] [--players N] [--games G] [--seed S]
+                          [--playouts P] [--time-ms T] [--threads T] [--rollout R]
+    regicide-cli self-play --output FILE [--players N] [--games G] [--seed S]
+                           [--playouts P] [--time-ms T] [--threads T] [--rollout R]
+
+Commands:
+    play        Play a game interactively, choosing the actions of every player
+    simulate    Let a built-in player play a batch of games and print the results
+    self-play   Let the mcts player play a batch of games and write the encoded
+                states, visit distributions and results to a .npy file
+
+Options:
+    --players N     Number of players from 1 to 4 [default: 2]
+    --seed S        Seed of the first game, game i uses seed S + i [default: random]
+    --games G       Number of games to simulate [default: 100]
+    --player KIND   Either random or mcts [default: random]
+    --playouts P    Playouts per action of the mcts player [default: 1000]
+    --time-ms T     Search for T milliseconds per action instead of a number of playouts
+    --threads T     Threads used by the mcts player [default: 1]
+    --rollout R     Rollout policy of the mcts player, one of uniform, no-diamond-discards,
+                    greedy-kill, minimal-damage-discard, immunity-aware and
+                    epsilon-greedy[:EPSILON] [default: uniform]
+    --heuristics    Same as --rollout no-diamond-discards
+    --output FILE   Where self-play writes its samples";
+
+enum Command {
+    Play,
+    Simulate,
+    SelfPlay,
+}
+
+enum PlayerKind {
+    Random,
+    Mcts,
+}
+
+struct Options {
+    command: Command,
+    num_players: usize,
+    seed: u64,
+    games: u64,
+    player: PlayerKind,
+    playouts: u32,
+    time_ms: Option<u64>,
+    threads: usize,
+    rollout: Rollout,
+    output: Option<String>,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut args = args.iter();
+        let command = match args.next().map(String::as_str) {
+            Some("play") => Command::Play,
+            Some("simulate") => Command::Simulate,
+            Some("self-play") => Command::SelfPlay,
+            Some(other) => return Err(format!("Unknown command '{}'", other)),
+            None => return Err("Missing command".to_string()),
+        };
+
+        let mut options = Options {
+            command,
+            num_players: 2,
+            seed: rand::random(),
+            games: 100,
+            player: PlayerKind::Random,
+            playouts: 1000,
+            time_ms: None,
+            threads: 1,
+            rollout: Rollout::Uniform,
+            output: None,
+        };
+        while let Some(flag) = args.next() {
+            if flag == "--heuristics" {
+                options.rollout = Rollout::NoDiamondDiscards;
+                continue;
+            }
+            let value = args
+                .next()
+                .ok_or_else(|| format!("Missing value for '{}'", flag))?;
+            match flag.as_str() {
+                "--players" => options.num_players = parse_value(flag, value)?,
+                "--seed" => options.seed = parse_value(flag, value)?,
+                "--games" => options.games = parse_value(flag, value)?,
+                "--playouts" => options.playouts = parse_value(flag, value)?,
+                "--time-ms" => options.time_ms = Some(parse_value(flag, value)?),
+                "--threads" => options.threads = parse_value(flag, value)?,
+                "--output" => options.output = Some(value.clone()),
+                "--rollout" => options.rollout = parse_value(flag, value)?,
+                "--player" => {
+                    options.player = match value.as_str() {
+                        "random" => PlayerKind::Random,
+                        "mcts" => PlayerKind::Mcts,
+                        _ => return Err(format!("Unknown player '{}'", value)),
+                    }
+                }
+                _ => return Err(format!("Unknown option '{}'", flag)),
+            }
+        }
+        if matches!(options.command, Command::SelfPlay) && options.output.is_none() {
+            return Err("Missing '--output' for self-play".to_string());
+        }
+        if options.games == 0 {
+            return Err("'--games' must be at least 1".to_string());
+        }
+        if options.threads == 0 {
+            return Err("'--threads' must be at least 1".to_string());
+        }
+        Ok(options)
+    }
+
+    fn mcts_player(&self) -> MCTSPlayer {
+        MCTSPlayer::new(
+            self.playouts,
+            self.threads,
+            self.rollout,
+            None,
+            None,
+            Some(self.seed),
+            self.time_ms,
+        )
+    }
+}
+
+fn parse_value<T: FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value '{}' for '{}'", value, flag))
+}
+
+fn play_game<const N: usize, P: Play>(player: &mut P, seed: u64) -> GameResult {
+    let mut state = State::<N>::new(Some(seed)).unwrap();
+    loop {
+        let action = player.play(state);
+        state = match state.take_action(&action) {
+            GameStatus::InProgress(next_state) => next_state,
+            GameStatus::HasEnded(result) => return result,
+        };
+    }
+}
+
+/// Play one game for each seed, with `player` choosing the actions of every player.
+fn play_games<P: Play>(
+    player: &mut P,
+    num_players: usize,
+    seeds: impl Iterator<Item = u64>,
+) -> Result<Vec<GameResult>, RegicideError> {
+    let play = match num_players {
+        1 => play_game::<1, P>,
+        2 => play_game::<2, P>,
+        3 => play_game::<3, P>,
+        4 => play_game::<4, P>,
+        _ => return Err(RegicideError::WrongNumberOfPlayers),
+    };
+    Ok(seeds.map(|seed| play(player, seed)).collect())
+}
+
+/// Self-play one game for each seed and return the samples of every game.
+fn self_play_games(
+    player: &mut MCTSPlayer,
+    num_players: usize,
+    seeds: impl Iterator<Item = u64>,
+) -> Result<Vec<Vec<SelfPlaySample>>, RegicideError> {
+    let rules = RuleSet::official(num_players)?;
+    let play = match num_players {
+        1 => self_play_game::<1, MCTSPlayer>,
+        2 => self_play_game::<2, MCTSPlayer>,
+        3 => self_play_game::<3, MCTSPlayer>,
+        4 => self_play_game::<4, MCTSPlayer>,
+        _ => return Err(RegicideError::WrongNumberOfPlayers),
+    };
+    seeds.map(|seed| play(player, Some(seed), rules)).collect()
+}
+
+fn run(options: Options) -> Result<(), Box<dyn Error>> {
+    let seeds = (0..options.games).map(|i| options.seed.wrapping_add(i));
+    let results = match (&options.command, &options.player) {
+        (Command::Play, _) => play_games(
+            &mut InputPlayer::new(),
+            options.num_players,
+            std::iter::once(options.seed),
+        )?,
+        (Command::Simulate, PlayerKind::Random) => play_games(
+            &mut RandomPlayer::new(Some(options.seed)),
+            options.num_players,
+            seeds,
+        )?,
+        (Command::Simulate, PlayerKind::Mcts) => {
+            play_games(&mut options.mcts_player(), options.num_players, seeds)?
+        }
+        (Command::SelfPlay, _) => {
+            let games = self_play_games(&mut options.mcts_player(), options.num_players, seeds)?;
+            let samples = games.iter().flatten().cloned().collect::<Vec<_>>();
+            let path = options.output.as_deref().unwrap();
+            write_npy(&samples, BufWriter::new(File::create(path)?))?;
+            println!("Wrote {} samples to {}", samples.len(), path);
+            games.iter().map(|samples| samples[0].result).collect()
+        }
+    };
+
+    let wins = results
+        .iter()
+        .filter(|result| matches!(result, GameResult::Won(_)))
+        .count();
+    let total_reward: u64 = results.iter().map(|result| result.reward() as u64).sum();
+    println!(
+        "Won {} of {} games ({:.1}%)",
+        wins,
+        results.len(),
+        100.0 * wins as f64 / results.len() as f64
+    );
+    println!(
+        "Average reward: {:.2}",
+        total_reward as f64 / results.len() as f64
+    );
+    if options.num_players == 1 {
+        for outcome in [SoloOutcome::Gold, SoloOutcome::Silver, SoloOutcome::Bronze] {
+            let count = results
+                .iter()
+                .filter(|result| result.solo_outcome() == Some(outcome))
+                .count();
+            println!("{:?} victories: {}", outcome, count);
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let options = match Options::parse(&args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            exit(2);
+        }
+    };
+    if let Err(error) = run(options) {
+        eprintln!("{}", error);
+        exit(1);
+    }
+}
@@ -1,18 +1,30 @@
-use crate::{game::state::State, Action};
+use crate::game::{state::State, Action};
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
 use std::io::stdin;
 
 use super::Play;
 
-#[derive(Clone)]
-#[pyclass]
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "python", pyclass(module = "regicide.players"))]
 pub struct InputPlayer;
 
+impl InputPlayer {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg(feature = "python")]
 #[pymethods]
 impl InputPlayer {
     #[new]
-    fn new() -> Self {
-        Self
+    fn py_new() -> Self {
+        Self::new()
+    }
+
+    fn __reduce__(&self, py: Python) -> (PyObject, ()) {
+        (py.get_type::<Self>().into(), ())
     }
 }
 
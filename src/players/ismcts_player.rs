@@ -0,0 +1,292 @@
+use crate::game::rollout::Rollout;
+use crate::game::{state::State, Action, GameResult, GameStatus};
+#[cfg(feature = "python")]
+use crate::python::{PyAction, PyGameView};
+use itertools::Itertools;
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use rand::prelude::{SliceRandom, StdRng};
+use rand::SeedableRng;
+
+#[cfg(feature = "python")]
+use super::{pickled_seed, RolloutArg};
+use super::{rank_actions, Play, Search};
+
+type Visits = u64;
+type AvgRewards = f64;
+
+/// Single-observer Information Set MCTS. Unlike `MCTSPlayer`, which searches
+/// a separate tree for each determinization, every iteration samples a new
+/// determinization of the hidden information and all iterations share a
+/// single tree. Nodes are identified by the actions taken from the root, so
+/// statistics are collected for the information set of the player who has
+/// the turn rather than for one guess of the hidden cards.
+#[derive(Clone)]
+#[cfg_attr(feature = "python", pyclass(module = "regicide.players"))]
+pub struct ISMCTSPlayer {
+    playouts: u32,
+    exploration_constant: f64,
+    rollout: Rollout,
+    rng: StdRng,
+    ranked_actions: Option<Vec<(Action, Visits, AvgRewards)>>,
+}
+
+/// A node of the search tree, stored in an arena and referred to by index.
+struct Node {
+    /// The action leading to the node, with the cards in a canonical order.
+    /// `None` for the root.
+    action: Option<Action>,
+    children: Vec<usize>,
+    visits: Visits,
+    /// Number of iterations in which the action of the node was legal
+    availability: Visits,
+    sum_rewards: u64,
+}
+
+impl Node {
+    fn new(action: Option<Action>) -> Self {
+        Self {
+            action,
+            children: vec![],
+            visits: 0,
+            availability: 0,
+            sum_rewards: 0,
+        }
+    }
+}
+
+impl ISMCTSPlayer {
+    /// `exploration_constant` defaults to the one used by `MCTSPlayer`.
+    /// `rollout` chooses the actions in the rollouts of the search.
+    pub fn new(
+        playouts: u32,
+        rollout: Rollout,
+        exploration_constant: Option<f64>,
+        seed: Option<u64>,
+    ) -> Self {
+        let rng = match seed {
+            Some(s) => StdRng::seed_from_u64(s),
+            None => StdRng::from_rng(rand::thread_rng()).unwrap(),
+        };
+        Self {
+            playouts,
+            exploration_constant: exploration_constant
+                .unwrap_or(2_f64.sqrt() * GameResult::max_score() as f64),
+            rollout,
+            rng,
+            ranked_actions: None,
+        }
+    }
+
+    /// Run one iteration of the search on a new determinization of `root`.
+    fn iterate<const N_PLAYERS: usize>(&mut self, tree: &mut Vec<Node>, root: &State<N_PLAYERS>) {
+        let mut state = root.determinize(&mut self.rng);
+        let mut path = vec![0];
+        let mut node = 0;
+
+        // Selection and expansion
+        let result = loop {
+            let actions = state.get_action_space();
+            if actions.is_empty() {
+                break None;
+            }
+            let untried = actions
+                .iter()
+                .filter(|&&action| {
+                    let action = canonical(action);
+                    !tree[node]
+                        .children
+                        .iter()
+                        .any(|&child| tree[child].action == Some(action))
+                })
+                .copied()
+                .collect_vec();
+
+            let (child, action) = match untried.choose(&mut self.rng) {
+                Some(&action) => {
+                    tree.push(Node::new(Some(canonical(action))));
+                    let child = tree.len() - 1;
+                    tree[node].children.push(child);
+                    (child, action)
+                }
+                None => {
+                    let available = actions
+                        .iter()
+                        .map(|&action| canonical(action))
+                        .collect_vec();
+                    let children = tree[node]
+                        .children
+                        .iter()
+                        .copied()
+                        .filter(|&child| available.contains(&tree[child].action.unwrap()))
+                        .collect_vec();
+                    for &child in children.iter() {
+                        tree[child].availability += 1;
+                    }
+                    let child = self.select_child(tree, &children);
+                    (child, tree[child].action.unwrap())
+                }
+            };
+            let expanded = tree[child].visits == 0;
+            path.push(child);
+            node = child;
+
+            match state.take_action(&action) {
+                GameStatus::InProgress(next_state) => state = next_state,
+                GameStatus::HasEnded(result) => break Some(result),
+            }
+            if expanded {
+                break None;
+            }
+        };
+
+        // Simulation
+        let result = match result {
+            Some(result) => result,
+            None => state.rollout(&self.rollout, &mut self.rng),
+        };
+
+        // Backpropagation. The game is cooperative, so every player gets the same reward
+        let reward = result.reward() as u64;
+        for &index in path.iter() {
+            tree[index].visits += 1;
+            tree[index].sum_rewards += reward;
+        }
+    }
+
+    /// UCB1 where the number of parent visits is replaced by the number of
+    /// times the child was available.
+    fn select_child(&mut self, tree: &[Node], children: &[usize]) -> usize {
+        let exploration_constant = self.exploration_constant;
+        let score = |child: &usize| {
+            let node = &tree[*child];
+            let mean = node.sum_rewards as f64 / node.visits as f64;
+            let explore = (2.0 * (node.availability as f64).ln() / node.visits as f64).sqrt();
+            mean + exploration_constant * explore
+        };
+        let best_score = children.iter().map(score).fold(f64::NEG_INFINITY, f64::max);
+        *children
+            .iter()
+            .filter(|child| score(child) == best_score)
+            .collect_vec()
+            .choose(&mut self.rng)
+            .expect("No actions available to choose from")
+    }
+}
+
+/// Cards of combos and discards in a fixed order, since the order of the
+/// hands of other players differs between determinizations.
+fn canonical(action: Action) -> Action {
+    match action {
+        Action::Combo(mut cards) => {
+            cards.sort_by_key(|card| card.index());
+            Action::Combo(cards)
+        }
+        Action::Discard(mut cards) => {
+            cards.sort_by_key(|card| card.index());
+            Action::Discard(cards)
+        }
+        action => action,
+    }
+}
+
+impl Play for ISMCTSPlayer {
+    fn play<const N_PLAYERS: usize>(&mut self, state: State<N_PLAYERS>) -> Action {
+        let mut tree = vec![Node::new(None)];
+        for _ in 0..self.playouts {
+            self.iterate(&mut tree, &state);
+        }
+
+        let action_space = state.get_action_space();
+        let ranked_actions = rank_actions(tree[0].children.iter().map(|&child| {
+            let node = &tree[child];
+            // Return the action as it appears in the action space of the state
+            let action = *action_space
+                .iter()
+                .find(|&&action| Some(canonical(action)) == node.action)
+                .unwrap();
+            (action, node.visits, node.sum_rewards as f64)
+        }))
+        .into_iter()
+        .map(|(action, visits, sum_rewards)| (action, visits, sum_rewards / visits as f64))
+        .collect_vec();
+
+        let best_action = ranked_actions
+            .first()
+            .expect("No actions available to choose from")
+            .0;
+        self.ranked_actions = Some(ranked_actions);
+        best_action
+    }
+}
+
+impl Search for ISMCTSPlayer {
+    fn ranked_actions(&self) -> &[(Action, Visits, AvgRewards)] {
+        self.ranked_actions.as_deref().unwrap_or_default()
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl ISMCTSPlayer {
+    /// `rollout` is the name of a rollout policy like for `MCTSPlayer`, or a
+    /// bool for compatibility.
+    #[new]
+    fn py_new(
+        playouts: u32,
+        rollout: RolloutArg,
+        exploration_constant: Option<f64>,
+        seed: Option<u64>,
+    ) -> PyResult<Self> {
+        Ok(Self::new(
+            playouts,
+            rollout.into_rollout()?,
+            exploration_constant,
+            seed,
+        ))
+    }
+
+    /// Choose an action based on the given state
+    fn play(&mut self, py: Python, state: PyGameView) -> PyAction {
+        self.play_py(py, state)
+    }
+
+    /// List actions with associated stats, sorted by most visits in the search.
+    /// The first Action in the list is also what `play()` returned.
+    ///
+    /// # Returns
+    /// List of tuples in the form `(action, visits, avg_reward)`
+    #[pyo3(name = "ranked_actions")]
+    fn py_ranked_actions(&self) -> Vec<(PyAction, Visits, AvgRewards)> {
+        self.ranked_actions()
+            .iter()
+            .map(|&a| (a.0.into(), a.1, a.2))
+            .collect()
+    }
+
+    fn __reduce__(&self, py: Python) -> (PyObject, (u32, String, Option<f64>, u64)) {
+        let args = (
+            self.playouts,
+            self.rollout.to_string(),
+            Some(self.exploration_constant),
+            pickled_seed(&self.rng),
+        );
+        (py.get_type::<Self>().into(), args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: u64 = 1337;
+
+    #[test]
+    fn ismcts_player_plays_legal_action() {
+        let state = State::<3>::new(Some(SEED)).unwrap();
+        let mut player = ISMCTSPlayer::new(200, Rollout::Uniform, None, Some(SEED));
+        let action = player.play(state);
+        assert!(state.get_action_space().contains(&action));
+        assert_eq!(player.ranked_actions()[0].0, action);
+    }
+}
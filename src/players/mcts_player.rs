@@ -1,59 +1,124 @@
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
 use crate::game::policy::MyPolicy;
-use crate::PyState;
-use crate::{
-    game::{
-        state::{EmptyTable, MyEvaluator, MyMCTS, State},
-        GameResult,
-    },
-    Action, PyAction,
+use crate::game::rollout::Rollout;
+use crate::game::{
+    state::{MyEvaluator, MyMCTS, State},
+    Action, GameResult,
 };
-use itertools::Itertools;
+#[cfg(feature = "python")]
+use crate::python::{PyAction, PyGameView};
+use mcts::transposition_table::ApproxTable;
 use mcts::MCTSManager;
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
-use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
 
-use super::Play;
+#[cfg(feature = "python")]
+use super::{pickled_seed, RolloutArg};
+use super::{rank_actions, Play, Search};
 
 type Visits = u64;
 type SumRewards = u64;
 type AvgRewards = f64;
+/// Rough number of playouts per millisecond of a single thread, used to size the
+/// transposition table when the number of playouts is not known up front.
+const PLAYOUTS_PER_MS: u64 = 50;
+/// Upper bound on the size of the transposition table of a time budget.
+const MAX_TABLE_CAPACITY: u64 = 1 << 20;
+/// Arguments of `MCTSPlayer::new`, in order.
+#[cfg(feature = "python")]
+type ConstructorArgs = (
+    u32,
+    usize,
+    String,
+    Option<u8>,
+    u32,
+    Option<u64>,
+    Option<u64>,
+);
 
 #[derive(Clone)]
-#[pyclass]
+#[cfg_attr(feature = "python", pyclass(module = "regicide.players"))]
 pub struct MCTSPlayer {
     playouts: u32,
     deterministic_samples: u32,
     num_threads: usize,
-    use_heuristics: bool,
+    rollout: Rollout,
     policy_variation: Option<u8>,
+    /// Seeds the determinizations and searches if the player was created with a seed
+    rng: Option<StdRng>,
+    /// Search for this many milliseconds per action instead of a fixed number of playouts
+    time_ms: Option<u64>,
     ranked_actions: Option<Vec<(Action, Visits, AvgRewards)>>,
+    playouts_done: u64,
 }
 
-#[pymethods]
 impl MCTSPlayer {
-    #[new]
-    fn new(
+    /// With a `seed`, the actions chosen by the player are reproducible as
+    /// long as it uses a single thread and no `time_ms`. With `time_ms`, the
+    /// time is split evenly across the `deterministic_samples` and `playouts`
+    /// is ignored. `rollout` selects the built-in policy choosing the actions
+    /// in the rollouts of the search.
+    pub fn new(
         playouts: u32,
         num_threads: usize,
-        use_heuristics: bool,
+        rollout: Rollout,
         policy_variation: Option<u8>,
         deterministic_samples: Option<u32>,
+        seed: Option<u64>,
+        time_ms: Option<u64>,
     ) -> Self {
         Self {
             playouts,
             deterministic_samples: deterministic_samples.unwrap_or(1),
             num_threads,
-            use_heuristics,
+            rollout,
             policy_variation,
+            rng: seed.map(StdRng::seed_from_u64),
+            time_ms,
             ranked_actions: None,
+            playouts_done: 0,
         }
     }
 
+    /// Total number of playouts of the last call to `play()`, across all
+    /// deterministic samples.
+    pub fn playouts_done(&self) -> u64 {
+        self.playouts_done
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl MCTSPlayer {
+    /// `rollout` is the name of a rollout policy, or a bool for compatibility.
+    #[new]
+    fn py_new(
+        playouts: u32,
+        num_threads: usize,
+        rollout: RolloutArg,
+        policy_variation: Option<u8>,
+        deterministic_samples: Option<u32>,
+        seed: Option<u64>,
+        time_ms: Option<u64>,
+    ) -> PyResult<Self> {
+        Ok(Self::new(
+            playouts,
+            num_threads,
+            rollout.into_rollout()?,
+            policy_variation,
+            deterministic_samples,
+            seed,
+            time_ms,
+        ))
+    }
+
     /// Choose an action based on the given state
-    fn play(&mut self, state: PyState) -> PyAction {
-        self.play_py(state)
+    fn play(&mut self, py: Python, state: PyGameView) -> PyAction {
+        self.play_py(py, state)
     }
 
     /// List actions with associated stats, sorted by most visits in the MCTS.
@@ -64,28 +129,50 @@ impl MCTSPlayer {
     ///
     /// # Returns
     /// List of tuples in the form `(action, visits, avg_reward)`
-    fn ranked_actions(&self) -> Vec<(PyAction, Visits, AvgRewards)> {
-        match &self.ranked_actions {
-            Some(vec) => vec.iter().map(|&a| (a.0.into(), a.1, a.2)).collect(),
-            None => vec![],
-        }
+    #[pyo3(name = "ranked_actions")]
+    fn py_ranked_actions(&self) -> Vec<(PyAction, Visits, AvgRewards)> {
+        self.ranked_actions()
+            .iter()
+            .map(|&a| (a.0.into(), a.1, a.2))
+            .collect()
+    }
+
+    /// Total number of playouts of the last call to `play()`. Useful to see
+    /// how much searching fitted in the time budget.
+    #[pyo3(name = "playouts_done")]
+    fn py_playouts_done(&self) -> u64 {
+        self.playouts_done
+    }
+
+    /// An unseeded player stays unseeded when unpickled.
+    fn __reduce__(&self, py: Python) -> (PyObject, ConstructorArgs) {
+        let args = (
+            self.playouts,
+            self.num_threads,
+            self.rollout.to_string(),
+            self.policy_variation,
+            self.deterministic_samples,
+            self.rng.as_ref().map(pickled_seed),
+            self.time_ms,
+        );
+        (py.get_type::<Self>().into(), args)
     }
 }
 
 impl Play for MCTSPlayer {
     fn play<const N_PLAYERS: usize>(&mut self, state: State<N_PLAYERS>) -> Action {
-        match self.use_heuristics {
-            true => self.play_generic::<N_PLAYERS, true>(state),
-            false => self.play_generic::<N_PLAYERS, false>(state),
-        }
+        self.play_generic(state)
+    }
+}
+
+impl Search for MCTSPlayer {
+    fn ranked_actions(&self) -> &[(Action, Visits, AvgRewards)] {
+        self.ranked_actions.as_deref().unwrap_or_default()
     }
 }
 
 impl MCTSPlayer {
-    fn play_generic<const N_PLAYERS: usize, const USE_HEURISTICS: bool>(
-        &mut self,
-        state: State<N_PLAYERS>,
-    ) -> Action {
+    fn play_generic<const N_PLAYERS: usize>(&mut self, state: State<N_PLAYERS>) -> Action {
         let policy = match self.policy_variation {
             None | Some(0) => MyPolicy::UCTBase {
                 exploration_constant: 2_f64.sqrt() * GameResult::max_score() as f64,
@@ -105,19 +192,45 @@ impl MCTSPlayer {
         };
 
         let mut meta_actions: HashMap<Action, (Visits, SumRewards)> = HashMap::new();
+        let mut playouts_done = 0;
 
-        let mut rng = rand::rngs::StdRng::from_rng(rand::thread_rng()).unwrap();
-        for _ in 0..self.deterministic_samples {
+        let deadline = self
+            .time_ms
+            .map(|time_ms| Instant::now() + Duration::from_millis(time_ms));
+        let mut rng = match self.rng.as_mut() {
+            Some(rng) => StdRng::seed_from_u64(rng.next_u64()),
+            None => StdRng::from_rng(rand::thread_rng()).unwrap(),
+        };
+        // Every playout adds at most one node to the tree. A table that turns
+        // out too small only means that some transpositions are not shared
+        let expected_playouts = match self.time_ms {
+            Some(time_ms) => (time_ms / self.deterministic_samples.max(1) as u64)
+                .saturating_mul(PLAYOUTS_PER_MS * self.num_threads.max(1) as u64)
+                .clamp(1, MAX_TABLE_CAPACITY),
+            None => self.playouts as u64,
+        };
+        let table_capacity = (expected_playouts as usize).next_power_of_two();
+        for sample in 0..self.deterministic_samples {
             let permuted_state = state.random_permutation(&mut rng);
+            let search_seed = self.rng.as_mut().map(|rng| rng.next_u64());
             let mut mcts = MCTSManager::new(
                 permuted_state,
                 // state,
-                MyMCTS::<N_PLAYERS, USE_HEURISTICS>,
-                MyEvaluator,
+                MyMCTS::<N_PLAYERS>::new(search_seed),
+                MyEvaluator::new(self.rollout),
                 policy.clone(),
-                EmptyTable,
+                ApproxTable::new(table_capacity),
             );
-            mcts.playout_n_parallel(self.playouts, self.num_threads);
+            match deadline {
+                Some(deadline) => {
+                    // Split the remaining time evenly, so that samples are not
+                    // cut short if an earlier sample went over its share
+                    let samples_left = self.deterministic_samples - sample;
+                    let time_left = deadline.saturating_duration_since(Instant::now());
+                    mcts.playout_parallel_for(time_left / samples_left, self.num_threads);
+                }
+                None => mcts.playout_n_parallel(self.playouts, self.num_threads),
+            }
             let root = mcts.tree().root_node();
 
             for move_info in root.moves().into_iter() {
@@ -125,30 +238,76 @@ impl MCTSPlayer {
                 let (visits, sum_rewards) = meta_actions.entry(*action).or_insert((0, 0));
                 *visits += move_info.visits();
                 *sum_rewards += move_info.sum_rewards() as SumRewards;
+                playouts_done += move_info.visits();
             }
         }
 
-        let actions = meta_actions
-            .iter()
-            .sorted_by_key(|(_, (_, sum_rewards))| -(*sum_rewards as i64))
-            .sorted_by_key(|(_, (visits, _))| -(*visits as i64))
-            .collect_vec();
+        let actions = rank_actions(
+            meta_actions
+                .into_iter()
+                .map(|(action, (visits, sum_rewards))| (action, visits, sum_rewards as f64)),
+        );
 
         let best_action = actions
-            .get(0)
+            .first()
             .expect("No actions available to choose from")
             .0;
 
         // Store ranked moves
+        self.playouts_done = playouts_done;
         self.ranked_actions = Some(
             actions
                 .into_iter()
-                .map(|(&action, &(visits, sum_rewards))| {
-                    (action, visits, sum_rewards as f64 / visits as f64)
+                .map(|(action, visits, sum_rewards)| {
+                    let avg_rewards = match visits {
+                        // Possible when the time budget runs out
+                        0 => 0.0,
+                        _ => sum_rewards / visits as f64,
+                    };
+                    (action, visits, avg_rewards)
                 })
                 .collect(),
         );
 
-        *best_action
+        best_action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::player::PlayerId;
+    use mcts::transposition_table::TranspositionHash;
+
+    const SEED: u64 = 1337;
+
+    #[test]
+    fn seeded_mcts_player_is_deterministic() {
+        let state = State::<2>::new(Some(SEED)).unwrap();
+        let ranked_actions = || {
+            let mut player =
+                MCTSPlayer::new(50, 1, Rollout::Uniform, None, Some(2), Some(SEED), None);
+            player.play(state);
+            player.ranked_actions().to_vec()
+        };
+        assert_eq!(ranked_actions(), ranked_actions());
+    }
+
+    #[test]
+    fn transposition_hash_ignores_seed_and_hand_order() {
+        let state = State::<2>::new(Some(SEED)).unwrap();
+        let mut transposed = state;
+        transposed.set_rng_seed(SEED.wrapping_add(1));
+        transposed.players_mut()[0].hand.reverse();
+        assert_eq!(
+            TranspositionHash::hash(&state),
+            TranspositionHash::hash(&transposed)
+        );
+
+        transposed.set_has_turn(PlayerId(1));
+        assert_ne!(
+            TranspositionHash::hash(&state),
+            TranspositionHash::hash(&transposed)
+        );
     }
 }
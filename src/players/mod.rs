@@ -1,22 +1,94 @@
-use crate::{
-    game::{state::State, Action},
-    PyAction, PyState, StateEnum,
-};
+#[cfg(feature = "python")]
+use crate::error::RegicideError;
+#[cfg(feature = "python")]
+use crate::game::rollout::Rollout;
+use crate::game::{state::State, Action};
+#[cfg(feature = "python")]
+use crate::python::{PyAction, PyGameView, StateEnum};
+#[cfg(feature = "python")]
+use pyo3::{exceptions::PyValueError, FromPyObject, PyResult, Python};
+#[cfg(feature = "python")]
+use rand::{rngs::StdRng, RngCore};
+use std::cmp::Ordering;
 
 pub trait Play {
     fn play<const N: usize>(&mut self, state: State<N>) -> Action;
 
-    fn play_py(&mut self, state: PyState) -> PyAction {
-        match state.state_enum {
+    /// Plays without holding the GIL, so other Python threads can run
+    /// while the player is thinking.
+    #[cfg(feature = "python")]
+    fn play_py(&mut self, py: Python, state: PyGameView) -> PyAction
+    where
+        Self: Send,
+    {
+        let state_enum = state.state_enum();
+        py.allow_threads(|| match state_enum {
             StateEnum::Players1(state) => self.play(state),
             StateEnum::Players2(state) => self.play(state),
             StateEnum::Players3(state) => self.play(state),
             StateEnum::Players4(state) => self.play(state),
-        }
+        })
         .into()
     }
 }
 
+/// A player that searches before choosing an action, and keeps statistics
+/// about the actions it considered.
+pub trait Search: Play {
+    /// Actions of the last call to `play()` along with their visits and
+    /// average reward, sorted by most visits.
+    fn ranked_actions(&self) -> &[(Action, u64, f64)];
+}
+
+/// Sort the statistics of a search in the order of `Search::ranked_actions`,
+/// breaking ties in visits by the highest reward. Remaining ties are ordered by
+/// notation, so that the ranking does not depend on how the statistics were collected.
+fn rank_actions(stats: impl IntoIterator<Item = (Action, u64, f64)>) -> Vec<(Action, u64, f64)> {
+    let mut ranked: Vec<_> = stats.into_iter().collect();
+    ranked.sort_by(|(action1, visits1, reward1), (action2, visits2, reward2)| {
+        visits2
+            .cmp(visits1)
+            .then(reward2.partial_cmp(reward1).unwrap_or(Ordering::Equal))
+            .then_with(|| action1.to_string().cmp(&action2.to_string()))
+    });
+    ranked
+}
+
+/// Seed of the unpickled copy of a player, used by the `__reduce__` of every
+/// player. Only the settings of a player are pickled, not the statistics of its
+/// last search, and the copy continues from the current state of the random
+/// number generator without advancing the original.
+#[cfg(feature = "python")]
+fn pickled_seed(rng: &StdRng) -> u64 {
+    rng.clone().next_u64()
+}
+
+/// The rollout policy given to the Python constructor of a player. Either the
+/// name of a policy, such as `"greedy-kill"` or `"epsilon-greedy:0.2"`, or a
+/// bool, where `True` avoids discarding Diamonds and `False` plays uniformly
+/// random rollouts.
+#[cfg(feature = "python")]
+#[derive(FromPyObject)]
+enum RolloutArg {
+    Heuristics(bool),
+    Name(String),
+}
+
+#[cfg(feature = "python")]
+impl RolloutArg {
+    fn into_rollout(self) -> PyResult<Rollout> {
+        match self {
+            RolloutArg::Heuristics(true) => Ok(Rollout::NoDiamondDiscards),
+            RolloutArg::Heuristics(false) => Ok(Rollout::Uniform),
+            RolloutArg::Name(name) => name
+                .parse()
+                .map_err(|e: RegicideError| PyValueError::new_err(e.to_string())),
+        }
+    }
+}
+
 pub mod input_player;
+pub mod ismcts_player;
 pub mod mcts_player;
+pub mod puct_player;
 pub mod random_player;
@@ -0,0 +1,581 @@
+use crate::error::RegicideError;
+use crate::game::action_index::ACTION_SPACE_SIZE;
+use crate::game::observation::Observation;
+use crate::game::rollout::Rollout;
+use crate::game::{state::State, Action, GameResult, GameStatus};
+#[cfg(feature = "python")]
+use crate::python::{PyAction, PyGameView, StateEnum};
+use itertools::Itertools;
+#[cfg(feature = "python")]
+use numpy::{PyArray1, PyArray2};
+#[cfg(feature = "python")]
+use pyo3::exceptions::PyValueError;
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use rand::prelude::StdRng;
+use rand::SeedableRng;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[cfg(feature = "python")]
+use super::{pickled_seed, RolloutArg};
+use super::{rank_actions, Play, Search};
+
+type Visits = u64;
+type AvgRewards = f64;
+/// Why a search could not be completed, e.g. the exception raised by a Python evaluator.
+pub type SearchError = Box<dyn std::error::Error + Send + Sync>;
+
+/// The output of a `PriorEvaluator` for a single observation.
+#[derive(Debug, Clone)]
+pub struct Evaluation {
+    /// Prior probabilities indexed by `Action::index`, i.e. of length
+    /// `ACTION_SPACE_SIZE`. Only the entries of legal actions are used, and
+    /// they are normalized to sum to 1.
+    pub priors: Vec<f32>,
+    /// Expected reward as a fraction of `GameResult::max_score()`, from 0 to 1.
+    /// If `None`, the value is estimated with a rollout instead.
+    pub value: Option<f32>,
+}
+
+/// Gives a prior over the legal actions and a value estimate for positions,
+/// e.g. through a neural network. Positions are evaluated in batches as seen
+/// by the player who has the turn.
+pub trait PriorEvaluator: Send + Sync {
+    /// Evaluate every observation, in order.
+    fn evaluate(&self, observations: &[Observation]) -> Result<Vec<Evaluation>, SearchError>;
+}
+
+/// Uniform priors with values from rollouts, which makes the search
+/// behave much like plain MCTS.
+pub struct UniformPrior;
+
+impl PriorEvaluator for UniformPrior {
+    fn evaluate(&self, observations: &[Observation]) -> Result<Vec<Evaluation>, SearchError> {
+        Ok(observations
+            .iter()
+            .map(|_| Evaluation {
+                priors: vec![1.0; ACTION_SPACE_SIZE],
+                value: None,
+            })
+            .collect())
+    }
+}
+
+/// AlphaZero-style search, where children are selected with the PUCT formula
+/// using the priors of a `PriorEvaluator`. Leaves are collected in batches
+/// using virtual loss, so that each batch takes a single call to the evaluator.
+#[derive(Clone)]
+#[cfg_attr(feature = "python", pyclass(module = "regicide.players"))]
+pub struct PuctPlayer {
+    playouts: u32,
+    deterministic_samples: u32,
+    batch_size: usize,
+    exploration_constant: f64,
+    /// Chooses the actions of the rollouts that estimate values the evaluator leaves out
+    rollout: Rollout,
+    evaluator: Arc<dyn PriorEvaluator>,
+    /// The Python callable behind `evaluator`, kept for pickling
+    #[cfg(feature = "python")]
+    callback: Option<PyObject>,
+    rng: StdRng,
+    ranked_actions: Option<Vec<(Action, Visits, AvgRewards)>>,
+}
+
+struct Node {
+    /// `None` for the root
+    action: Option<Action>,
+    prior: f32,
+    children: Vec<usize>,
+    expanded: bool,
+    /// Includes the virtual visits of leaves waiting for evaluation, which
+    /// count as a loss until their value is backpropagated.
+    visits: u32,
+    /// Sum of the values of every visit, from 0 to 1
+    value_sum: f64,
+}
+
+impl Node {
+    fn new(action: Option<Action>, prior: f32) -> Self {
+        Self {
+            action,
+            prior,
+            children: vec![],
+            expanded: false,
+            visits: 0,
+            value_sum: 0.0,
+        }
+    }
+}
+
+/// Where the selection of an iteration ended.
+enum Leaf<const N_PLAYERS: usize> {
+    Terminal(f64),
+    Unexpanded(State<N_PLAYERS>),
+}
+
+impl PuctPlayer {
+    /// `deterministic_samples` defaults to 1, `batch_size` to 8,
+    /// `exploration_constant` to 1.5 and `rollout` to `Rollout::Uniform`.
+    pub fn new(
+        playouts: u32,
+        evaluator: Arc<dyn PriorEvaluator>,
+        deterministic_samples: Option<u32>,
+        batch_size: Option<usize>,
+        exploration_constant: Option<f64>,
+        rollout: Option<Rollout>,
+        seed: Option<u64>,
+    ) -> Self {
+        let rng = match seed {
+            Some(s) => StdRng::seed_from_u64(s),
+            None => StdRng::from_rng(rand::thread_rng()).unwrap(),
+        };
+        Self {
+            playouts,
+            deterministic_samples: deterministic_samples.unwrap_or(1),
+            batch_size: batch_size.unwrap_or(8).max(1),
+            exploration_constant: exploration_constant.unwrap_or(1.5),
+            rollout: rollout.unwrap_or(Rollout::Uniform),
+            evaluator,
+            #[cfg(feature = "python")]
+            callback: None,
+            rng,
+            ranked_actions: None,
+        }
+    }
+
+    /// Choose an action like `Play::play`, but return the errors of the evaluator
+    /// instead of panicking. Fails with `RegicideError::NoPlayouts` if the player
+    /// has no playouts or deterministic samples to search with.
+    pub fn try_play<const N_PLAYERS: usize>(
+        &mut self,
+        state: State<N_PLAYERS>,
+    ) -> Result<Action, SearchError> {
+        if self.playouts == 0 || self.deterministic_samples == 0 {
+            return Err(RegicideError::NoPlayouts.into());
+        }
+        let mut meta_actions: HashMap<Action, (Visits, f64)> = HashMap::new();
+        for _ in 0..self.deterministic_samples {
+            let determinization = state.determinize(&mut self.rng);
+            let tree = self.search(determinization)?;
+            for &child in tree[0].children.iter() {
+                let (visits, value_sum) = meta_actions
+                    .entry(tree[child].action.unwrap())
+                    .or_insert((0, 0.0));
+                *visits += tree[child].visits as Visits;
+                *value_sum += tree[child].value_sum;
+            }
+        }
+
+        let ranked_actions = rank_actions(
+            meta_actions
+                .into_iter()
+                .map(|(action, (visits, value_sum))| (action, visits, value_sum)),
+        )
+        .into_iter()
+        .map(|(action, visits, value_sum)| {
+            let avg_rewards = match visits {
+                0 => 0.0,
+                _ => value_sum / visits as f64 * GameResult::max_score() as f64,
+            };
+            (action, visits, avg_rewards)
+        })
+        .collect_vec();
+
+        // Without legal actions the game is already lost
+        let best_action = ranked_actions.first().ok_or(RegicideError::GameHasEnded)?.0;
+        self.ranked_actions = Some(ranked_actions);
+        Ok(best_action)
+    }
+
+    /// Search a single determinization of the state.
+    fn search<const N_PLAYERS: usize>(
+        &mut self,
+        root_state: State<N_PLAYERS>,
+    ) -> Result<Vec<Node>, SearchError> {
+        let mut tree = vec![Node::new(None, 1.0)];
+        let mut playouts_done = 0;
+        while playouts_done < self.playouts {
+            let mut batch = vec![];
+            let batch_size = self
+                .batch_size
+                .min((self.playouts - playouts_done) as usize);
+            for _ in 0..batch_size {
+                let (path, leaf) = self.select_leaf(&mut tree, root_state);
+                match leaf {
+                    Leaf::Terminal(value) => {
+                        backpropagate(&mut tree, &path, value);
+                        playouts_done += 1;
+                    }
+                    Leaf::Unexpanded(state) => batch.push((path, state)),
+                }
+            }
+            if batch.is_empty() {
+                continue;
+            }
+
+            let observations = batch
+                .iter()
+                .map(|(_, state)| state.observation(state.has_turn()))
+                .collect_vec();
+            let evaluations = self.evaluator.evaluate(&observations)?;
+            if evaluations.len() != batch.len() {
+                return Err(format!(
+                    "Expected {} evaluations but got {}",
+                    batch.len(),
+                    evaluations.len()
+                )
+                .into());
+            }
+            for (((path, state), observation), evaluation) in
+                batch.into_iter().zip(observations).zip(evaluations)
+            {
+                let leaf = *path.last().unwrap();
+                if !tree[leaf].expanded {
+                    expand(&mut tree, leaf, &observation, &evaluation.priors);
+                }
+                let value = match evaluation.value {
+                    Some(value) => value as f64,
+                    None => {
+                        let result = state.rollout(&self.rollout, &mut self.rng);
+                        result.reward() as f64 / GameResult::max_score() as f64
+                    }
+                };
+                backpropagate(&mut tree, &path, value);
+                playouts_done += 1;
+            }
+        }
+        Ok(tree)
+    }
+
+    /// Follow the children with the highest PUCT score until reaching a leaf,
+    /// adding a virtual visit to every node on the way.
+    fn select_leaf<const N_PLAYERS: usize>(
+        &self,
+        tree: &mut [Node],
+        mut state: State<N_PLAYERS>,
+    ) -> (Vec<usize>, Leaf<N_PLAYERS>) {
+        let mut path = vec![0];
+        let mut node = 0;
+        tree[node].visits += 1;
+        loop {
+            if !tree[node].expanded {
+                return (path, Leaf::Unexpanded(state));
+            }
+            if tree[node].children.is_empty() {
+                // No legal actions, so the game is lost
+                let value = state.reward() as f64 / GameResult::max_score() as f64;
+                return (path, Leaf::Terminal(value));
+            }
+
+            let parent_visits = (tree[node].visits as f64).sqrt();
+            let child = *tree[node]
+                .children
+                .iter()
+                .max_by(|&&a, &&b| {
+                    let score = |child: usize| {
+                        let child = &tree[child];
+                        let mean = match child.visits {
+                            0 => 0.0,
+                            visits => child.value_sum / visits as f64,
+                        };
+                        let explore =
+                            child.prior as f64 * parent_visits / (1 + child.visits) as f64;
+                        mean + self.exploration_constant * explore
+                    };
+                    score(a).total_cmp(&score(b))
+                })
+                .unwrap();
+            path.push(child);
+            node = child;
+            tree[node].visits += 1;
+
+            match state.take_action(&tree[node].action.unwrap()) {
+                GameStatus::InProgress(next_state) => state = next_state,
+                GameStatus::HasEnded(result) => {
+                    let value = result.reward() as f64 / GameResult::max_score() as f64;
+                    return (path, Leaf::Terminal(value));
+                }
+            }
+        }
+    }
+}
+
+/// Add a child for each legal action, with the priors normalized over them.
+fn expand(tree: &mut Vec<Node>, node: usize, observation: &Observation, priors: &[f32]) {
+    let actions = observation.action_space();
+    let legal_priors = actions
+        .iter()
+        .map(|action| {
+            observation
+                .action_index(action)
+                .and_then(|index| priors.get(index).copied())
+                .unwrap_or(0.0)
+                .max(0.0)
+        })
+        .collect_vec();
+    let total: f32 = legal_priors.iter().sum();
+
+    for (action, prior) in actions.iter().zip(legal_priors) {
+        let prior = match total > 0.0 {
+            true => prior / total,
+            false => 1.0 / actions.len() as f32,
+        };
+        tree.push(Node::new(Some(*action), prior));
+        let child = tree.len() - 1;
+        tree[node].children.push(child);
+    }
+    tree[node].expanded = true;
+}
+
+/// Turn the virtual visits along `path` into real visits of `value`.
+fn backpropagate(tree: &mut [Node], path: &[usize], value: f64) {
+    for &node in path {
+        tree[node].value_sum += value;
+    }
+}
+
+impl Play for PuctPlayer {
+    /// Panics if the evaluator fails, see `try_play`.
+    fn play<const N_PLAYERS: usize>(&mut self, state: State<N_PLAYERS>) -> Action {
+        self.try_play(state)
+            .unwrap_or_else(|err| panic!("The search failed: {}", err))
+    }
+}
+
+impl Search for PuctPlayer {
+    fn ranked_actions(&self) -> &[(Action, Visits, AvgRewards)] {
+        self.ranked_actions.as_deref().unwrap_or_default()
+    }
+}
+
+/// A `PriorEvaluator` that calls a Python function with a batch of encoded
+/// observations and their action masks.
+#[cfg(feature = "python")]
+struct PyPriorEvaluator {
+    callback: PyObject,
+}
+
+#[cfg(feature = "python")]
+impl PyPriorEvaluator {
+    fn call(&self, py: Python, observations: &[Observation]) -> PyResult<Vec<Evaluation>> {
+        let encodings = observations.iter().map(Observation::encode).collect_vec();
+        let masks = observations
+            .iter()
+            .map(Observation::action_mask)
+            .collect_vec();
+        let args = (
+            crate::vec_env::to_array2(py, &encodings)?,
+            crate::vec_env::to_array2(py, &masks)?,
+        );
+        let (priors, values): (&PyArray2<f32>, Option<&PyArray1<f32>>) =
+            self.callback.call1(py, args)?.into_ref(py).extract()?;
+
+        let priors = priors.readonly();
+        let priors = priors
+            .as_array()
+            .rows()
+            .into_iter()
+            .map(|row| row.to_vec())
+            .collect_vec();
+        let values: Vec<Option<f32>> = match values {
+            Some(values) => values
+                .readonly()
+                .as_array()
+                .iter()
+                .map(|&v| Some(v))
+                .collect(),
+            None => vec![None; observations.len()],
+        };
+        if priors.len() != observations.len() || values.len() != observations.len() {
+            return Err(PyValueError::new_err(format!(
+                "Expected priors and values for {} observations but got {} and {}",
+                observations.len(),
+                priors.len(),
+                values.len()
+            )));
+        }
+        Ok(priors
+            .into_iter()
+            .zip(values)
+            .map(|(priors, value)| Evaluation { priors, value })
+            .collect())
+    }
+}
+
+#[cfg(feature = "python")]
+impl PriorEvaluator for PyPriorEvaluator {
+    fn evaluate(&self, observations: &[Observation]) -> Result<Vec<Evaluation>, SearchError> {
+        Ok(Python::with_gil(|py| self.call(py, observations))?)
+    }
+}
+
+/// The exception raised by the evaluator, or a `ValueError` if the search failed
+/// for another reason, such as an evaluator returning the wrong number of evaluations.
+#[cfg(feature = "python")]
+pub(crate) fn search_error_to_py(err: SearchError) -> PyErr {
+    match err.downcast::<PyErr>() {
+        Ok(err) => *err,
+        Err(err) => PyValueError::new_err(err.to_string()),
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl PuctPlayer {
+    /// `evaluator` is called with a batch of encoded observations of shape
+    /// `(batch, OBSERVATION_SIZE)` and their action masks of shape
+    /// `(batch, ACTION_SPACE_SIZE)`. It must return a tuple `(priors, values)`,
+    /// where `priors` has the same shape as the masks and `values` holds the
+    /// expected reward of each observation as a fraction of the maximum score.
+    /// `values` may be `None` to use rollouts instead, with the actions chosen
+    /// by `rollout` like for `MCTSPlayer`. Both arrays must have dtype float32.
+    /// Without an evaluator, priors are uniform and values come from rollouts.
+    #[new]
+    fn py_new(
+        playouts: u32,
+        evaluator: Option<PyObject>,
+        deterministic_samples: Option<u32>,
+        batch_size: Option<usize>,
+        exploration_constant: Option<f64>,
+        rollout: Option<RolloutArg>,
+        seed: Option<u64>,
+    ) -> PyResult<Self> {
+        let prior_evaluator: Arc<dyn PriorEvaluator> = match &evaluator {
+            Some(callback) => Arc::new(PyPriorEvaluator {
+                callback: callback.clone(),
+            }),
+            None => Arc::new(UniformPrior),
+        };
+        Ok(Self {
+            callback: evaluator,
+            ..Self::new(
+                playouts,
+                prior_evaluator,
+                deterministic_samples,
+                batch_size,
+                exploration_constant,
+                rollout.map(RolloutArg::into_rollout).transpose()?,
+                seed,
+            )
+        })
+    }
+
+    /// Choose an action based on the given state. Exceptions raised by the
+    /// evaluator are passed on.
+    fn play(&mut self, py: Python, state: PyGameView) -> PyResult<PyAction> {
+        let state_enum = state.state_enum();
+        py.allow_threads(|| match state_enum {
+            StateEnum::Players1(state) => self.try_play(state),
+            StateEnum::Players2(state) => self.try_play(state),
+            StateEnum::Players3(state) => self.try_play(state),
+            StateEnum::Players4(state) => self.try_play(state),
+        })
+        .map(PyAction::from)
+        .map_err(search_error_to_py)
+    }
+
+    /// List actions with associated stats, sorted by most visits in the search.
+    /// The first Action in the list is also what `play()` returned.
+    ///
+    /// # Returns
+    /// List of tuples in the form `(action, visits, avg_reward)`
+    #[pyo3(name = "ranked_actions")]
+    fn py_ranked_actions(&self) -> Vec<(PyAction, Visits, AvgRewards)> {
+        self.ranked_actions()
+            .iter()
+            .map(|&a| (a.0.into(), a.1, a.2))
+            .collect()
+    }
+
+    /// The evaluator is pickled along with the player, so it must be picklable itself.
+    fn __reduce__(&self, py: Python) -> (PyObject, ConstructorArgs) {
+        let args = (
+            self.playouts,
+            self.callback.clone(),
+            Some(self.deterministic_samples),
+            Some(self.batch_size),
+            Some(self.exploration_constant),
+            self.rollout.to_string(),
+            pickled_seed(&self.rng),
+        );
+        (py.get_type::<Self>().into(), args)
+    }
+}
+
+/// Arguments of the Python constructor of `PuctPlayer`, see `__reduce__`.
+#[cfg(feature = "python")]
+type ConstructorArgs = (
+    u32,
+    Option<PyObject>,
+    Option<u32>,
+    Option<usize>,
+    Option<f64>,
+    String,
+    u64,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: u64 = 1337;
+
+    #[test]
+    fn puct_player_plays_legal_action() {
+        let state = State::<2>::new(Some(SEED)).unwrap();
+        let mut player = PuctPlayer::new(
+            200,
+            Arc::new(UniformPrior),
+            None,
+            None,
+            None,
+            None,
+            Some(SEED),
+        );
+        let action = player.play(state);
+        assert!(state.get_action_space().contains(&action));
+        assert_eq!(player.ranked_actions()[0].0, action);
+    }
+
+    #[test]
+    fn evaluator_errors_are_returned() {
+        struct EmptyEvaluator;
+        impl PriorEvaluator for EmptyEvaluator {
+            fn evaluate(&self, _: &[Observation]) -> Result<Vec<Evaluation>, SearchError> {
+                Ok(vec![])
+            }
+        }
+
+        let state = State::<2>::new(Some(SEED)).unwrap();
+        let mut player = PuctPlayer::new(
+            20,
+            Arc::new(EmptyEvaluator),
+            None,
+            None,
+            None,
+            None,
+            Some(SEED),
+        );
+        assert!(player.try_play(state).is_err());
+    }
+
+    #[test]
+    fn zero_playouts_are_rejected() {
+        let state = State::<2>::new(Some(SEED)).unwrap();
+        let mut player = PuctPlayer::new(
+            0,
+            Arc::new(UniformPrior),
+            None,
+            None,
+            None,
+            None,
+            Some(SEED),
+        );
+        let err = player.try_play(state).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<RegicideError>(),
+            Some(RegicideError::NoPlayouts)
+        ));
+    }
+}
@@ -1,6 +1,10 @@
+#[cfg(feature = "python")]
+use super::pickled_seed;
 use super::Play;
-use crate::{game::state::State, Action};
-use crate::{PyAction, PyState};
+use crate::game::{state::State, Action};
+#[cfg(feature = "python")]
+use crate::python::{PyAction, PyGameView};
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
 use rand::{
     prelude::{SliceRandom, StdRng},
@@ -8,7 +12,7 @@ use rand::{
 };
 
 #[derive(Clone)]
-#[pyclass]
+#[cfg_attr(feature = "python", pyclass(module = "regicide.players"))]
 pub struct RandomPlayer {
     rng: StdRng,
 }
@@ -19,18 +23,29 @@ impl Play for RandomPlayer {
     }
 }
 
-#[pymethods]
 impl RandomPlayer {
-    #[new]
-    fn new(seed: Option<u64>) -> Self {
+    pub fn new(seed: Option<u64>) -> Self {
         let rng = match seed {
             Some(s) => StdRng::seed_from_u64(s),
             None => StdRng::from_rng(rand::thread_rng()).unwrap(),
         };
         Self { rng }
     }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl RandomPlayer {
+    #[new]
+    fn py_new(seed: Option<u64>) -> Self {
+        Self::new(seed)
+    }
+
+    fn play(&mut self, py: Python, state: PyGameView) -> PyAction {
+        self.play_py(py, state)
+    }
 
-    fn play(&mut self, state: PyState) -> PyAction {
-        self.play_py(state)
+    fn __reduce__(&self, py: Python) -> (PyObject, (u64,)) {
+        (py.get_type::<Self>().into(), (pickled_seed(&self.rng),))
     }
 }
@@ -0,0 +1,1074 @@
+use crate::aec_env::RegicideAECEnv;
+use crate::game::action_index::ACTION_SPACE_SIZE;
+use crate::game::card::{Card, CardSuit, CardValue, Hand};
+use crate::game::encoding::OBSERVATION_SIZE;
+use crate::game::enemy::Enemy;
+use crate::game::observation::Observation;
+use crate::game::player::PlayerId;
+use crate::game::record::{GameRecord, RecordStep};
+use crate::game::rules::RuleSet;
+use crate::game::state::{ActionType, State};
+use crate::game::table::Table;
+use crate::game::{Action, GameResult, SoloOutcome};
+use crate::players::{
+    input_player::InputPlayer,
+    ismcts_player::ISMCTSPlayer,
+    mcts_player::MCTSPlayer,
+    puct_player::{search_error_to_py, PuctPlayer},
+    random_player::RandomPlayer,
+    Play,
+};
+use crate::vec_env::VecRegicideEnv;
+use numpy::PyArray1;
+use pyo3::exceptions::{PyKeyError, PyTypeError, PyValueError};
+use pyo3::types::{IntoPyDict, PyTuple};
+use pyo3::{prelude::*, AsPyPointer};
+use rand::prelude::StdRng;
+use rand::SeedableRng;
+
+/// A macro for a match statement that calls the same function
+/// with the inner state for each variant of StateEnum
+macro_rules! state_enum_repeat {
+    ($to_match:expr, $to_repeat:expr $(, $additional_args:expr)*) => {
+        match $to_match {
+            StateEnum::Players1(state) => $to_repeat(state $(, $additional_args)*),
+            StateEnum::Players2(state) => $to_repeat(state $(, $additional_args)*),
+            StateEnum::Players3(state) => $to_repeat(state $(, $additional_args)*),
+            StateEnum::Players4(state) => $to_repeat(state $(, $additional_args)*),
+        }
+    };
+}
+
+#[pyclass(module = "regicide")]
+#[pyo3(name = "GameResult")]
+#[derive(Debug, Clone)]
+pub(crate) enum PyGameResult {
+    Won,
+    Lost,
+}
+
+impl From<GameResult> for PyGameResult {
+    fn from(result: GameResult) -> Self {
+        match result {
+            GameResult::Won(_) => PyGameResult::Won,
+            GameResult::Lost(_) => PyGameResult::Lost,
+        }
+    }
+}
+
+#[pyclass(module = "regicide")]
+struct RegicideGame {
+    state: PyState,
+    players: Vec<PyPlayer>,
+    result: Option<GameResult>,
+    /// Used to sample the hidden information of observations handed to players
+    rng: StdRng,
+    record: GameRecord,
+}
+
+impl RegicideGame {
+    fn with_players(
+        players: Vec<PyPlayer>,
+        num_players: usize,
+        seed: Option<u64>,
+        rules: Option<RuleSet>,
+    ) -> PyResult<Self> {
+        // A seed is always used so that the game can be replayed from its record
+        let seed = seed.unwrap_or_else(rand::random);
+        let rules = rules_or_official(num_players, rules)?;
+        Ok(Self {
+            state: PyState {
+                state_enum: StateEnum::with_rules(num_players, Some(seed), rules)?,
+            },
+            players,
+            result: None,
+            rng: new_rng(Some(seed)),
+            record: GameRecord::new(num_players, seed, rules),
+        })
+    }
+
+    /// Take an action, which must be legal, and add it to the record.
+    ///
+    /// # Returns
+    /// The increase in reward and the result of the game if it has ended
+    fn take_recorded_action(&mut self, action: &Action) -> (u8, Option<GameResult>) {
+        let player = self.state.state_enum.has_turn();
+        let hands_before = self.state.state_enum.hands();
+        let (reward, result) = self.state.state_enum.step(action);
+
+        let step = match result {
+            Some(_) => RecordStep::new(player, *action, None, &hands_before, None),
+            None => RecordStep::new(
+                player,
+                *action,
+                self.state.state_enum.current_enemy(),
+                &hands_before,
+                Some(&self.state.state_enum.hands()[..]),
+            ),
+        };
+        self.record.push(step);
+        if let Some(result) = result {
+            self.record.finish(result);
+        }
+        self.result = result;
+        (reward, result)
+    }
+}
+
+/// `rules` if given, and otherwise the official rules for the number of players.
+pub(crate) fn rules_or_official(num_players: usize, rules: Option<RuleSet>) -> PyResult<RuleSet> {
+    match rules {
+        Some(rules) => Ok(rules),
+        None => RuleSet::official(num_players).map_err(|e| PyValueError::new_err(e.to_string())),
+    }
+}
+
+pub(crate) fn new_rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(s) => StdRng::seed_from_u64(s),
+        None => StdRng::from_rng(rand::thread_rng()).unwrap(),
+    }
+}
+
+#[pymethods]
+impl RegicideGame {
+    /// `rules` defaults to the official rules for the number of players.
+    #[new]
+    fn new(players: Vec<PyPlayer>, seed: Option<u64>, rules: Option<RuleSet>) -> PyResult<Self> {
+        let num_players = players.len();
+        Self::with_players(players, num_players, seed, rules)
+    }
+
+    /// Create a game without any player objects. The game must be driven
+    /// through `reset()` and `step()` instead of `playout()`.
+    #[staticmethod]
+    fn environment(
+        num_players: usize,
+        seed: Option<u64>,
+        rules: Option<RuleSet>,
+    ) -> PyResult<Self> {
+        Self::with_players(vec![], num_players, seed, rules)
+    }
+
+    /// Start a new game with the same number of players and rules.
+    ///
+    /// # Returns
+    /// The initial observation
+    fn reset(&mut self, seed: Option<u64>) -> PyResult<PyState> {
+        let players = std::mem::take(&mut self.players);
+        let num_players = self.state.state_enum.num_players();
+        let rules = *self.record.rules();
+        *self = Self::with_players(players, num_players, seed, Some(rules))?;
+        Ok(self.state.clone())
+    }
+
+    /// Every action taken since the game was created or last reset.
+    fn record(&self) -> GameRecord {
+        self.record.clone()
+    }
+
+    /// Take an action on behalf of the player who has the turn.
+    ///
+    /// # Returns
+    /// Tuple in the form `(observation, reward, done, info)`, where `reward` is
+    /// the number of enemies defeated by the action and `info` is a dict with
+    /// the keys `result` and `has_turn`.
+    fn step(&mut self, py: Python, action: PyAction) -> PyResult<(PyState, u8, bool, PyObject)> {
+        if self.result.is_some() {
+            return Err(PyValueError::new_err(
+                "The game has ended. Call reset() to start a new game",
+            ));
+        }
+
+        let action: Action = action.into();
+        self.state.state_enum.validate_action(&action)?;
+        let (reward, result) = self.take_recorded_action(&action);
+
+        let info = [
+            ("result", result.map(PyGameResult::from).into_py(py)),
+            ("has_turn", self.state.has_turn()?.into_py(py)),
+        ]
+        .into_py_dict(py);
+
+        Ok((
+            self.state.clone(),
+            reward,
+            result.is_some(),
+            info.to_object(py),
+        ))
+    }
+
+    fn print(&self) {
+        dbg!(&self.state);
+    }
+
+    fn playout(&mut self, py: Python) -> PyResult<PyGameResult> {
+        loop {
+            let state_enum_clone = self.state.state_enum;
+            let player_id = self.state.has_turn()?;
+            let player = self.players.get_mut(player_id.0).ok_or_else(|| {
+                PyValueError::new_err(format!("No player object for {:?}", player_id))
+            })?;
+
+            let action = match player {
+                // Other Python threads can run while a Rust player is thinking
+                PyPlayer::Rust(rust_player) => {
+                    py.allow_threads(|| rust_player.play(state_enum_clone))?
+                }
+                PyPlayer::Python(python_obj) => {
+                    // Python players only get to see what they are allowed to see
+                    let py_observation = state_enum_clone.observe(&mut self.rng).into_py(py);
+                    // let args = PyTuple::new(py, &["$self"]);
+                    let args = PyTuple::empty(py);
+                    let kwargs = vec![("state", py_observation)].into_py_dict(py);
+                    python_obj
+                        // .call_method(py, "play", args, Some(kwargs))?
+                        .call_method(py, "play", args, Some(kwargs))?
+                        .extract::<PyAction>(py)?
+                        .into()
+                }
+            };
+
+            // Validate that the chosen action is legal
+            self.state.state_enum.validate_action(&action)?;
+
+            if let (_, Some(result)) = self.take_recorded_action(&action) {
+                return Ok(result.into());
+            }
+        }
+    }
+
+    fn reward(&self) -> usize {
+        self.state.reward().into()
+    }
+
+    /// The rating of a solo victory. `None` if the game was lost, is still in
+    /// progress or has more than one player.
+    fn solo_outcome(&self) -> Option<SoloOutcome> {
+        self.result.and_then(|result| result.solo_outcome())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(untagged)
+)]
+pub enum StateEnum {
+    Players1(State<1>),
+    Players2(State<2>),
+    Players3(State<3>),
+    Players4(State<4>),
+}
+
+impl StateEnum {
+    pub(crate) fn with_rules(
+        num_players: usize,
+        seed: Option<u64>,
+        rules: RuleSet,
+    ) -> Result<Self, PyErr> {
+        let state_enum = match num_players {
+            1 => State::with_rules(seed, rules).map(StateEnum::Players1),
+            2 => State::with_rules(seed, rules).map(StateEnum::Players2),
+            3 => State::with_rules(seed, rules).map(StateEnum::Players3),
+            4 => State::with_rules(seed, rules).map(StateEnum::Players4),
+            _ => return Err(PyValueError::new_err("Only 1-4 players are allowed")),
+        };
+        state_enum.map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    pub(crate) fn rules(&self) -> RuleSet {
+        *state_enum_repeat!(self, State::rules)
+    }
+
+    pub(crate) fn num_players(&self) -> usize {
+        match self {
+            StateEnum::Players1(_) => 1,
+            StateEnum::Players2(_) => 2,
+            StateEnum::Players3(_) => 3,
+            StateEnum::Players4(_) => 4,
+        }
+    }
+
+    pub(crate) fn reward(&self) -> u8 {
+        state_enum_repeat!(self, State::reward)
+    }
+
+    pub(crate) fn has_turn(&self) -> PlayerId {
+        state_enum_repeat!(self, State::has_turn)
+    }
+
+    pub(crate) fn solo_outcome(&self) -> Option<SoloOutcome> {
+        state_enum_repeat!(self, State::solo_outcome)
+    }
+
+    pub(crate) fn observation(&self, player: PlayerId) -> Observation {
+        state_enum_repeat!(self, State::observation, player)
+    }
+
+    pub(crate) fn table(&self) -> &Table {
+        state_enum_repeat!(self, State::table)
+    }
+
+    pub(crate) fn action_type(&self) -> ActionType {
+        state_enum_repeat!(self, State::action_type)
+    }
+
+    pub(crate) fn current_enemy(&self) -> Option<Enemy> {
+        state_enum_repeat!(self, State::current_enemy).copied()
+    }
+
+    /// The hand of every player, ordered by id.
+    pub(crate) fn hands(&self) -> Vec<Hand> {
+        fn hands_generic<const N: usize>(state: &State<N>) -> Vec<Hand> {
+            state.players().iter().map(|p| p.hand).collect()
+        }
+        state_enum_repeat!(self, hands_generic)
+    }
+
+    pub(crate) fn hand_sizes(&self) -> Vec<usize> {
+        self.hands().iter().map(|hand| hand.len()).collect()
+    }
+
+    pub(crate) fn encode(&self) -> Vec<f32> {
+        state_enum_repeat!(self, State::encode)
+    }
+
+    pub(crate) fn action_mask(&self) -> Vec<bool> {
+        state_enum_repeat!(self, State::action_mask)
+    }
+
+    pub(crate) fn action_from_index(&self, index: usize) -> Option<Action> {
+        state_enum_repeat!(self, State::action_from_index, index)
+    }
+
+    pub(crate) fn validate_action(&self, action: &Action) -> PyResult<()> {
+        let action_space = state_enum_repeat!(self, State::get_action_space);
+        if !action_space.contains(action) {
+            return Err(PyKeyError::new_err(format!(
+                "'{:?}' is not a legal action. Legal actions are: {:?}",
+                action, action_space
+            )));
+        }
+        Ok(())
+    }
+
+    /// The observation of the player who has the turn. The hidden information
+    /// of the state kept by the observation is resampled using `rng`.
+    pub(crate) fn observe(&self, rng: &mut StdRng) -> PyObservation {
+        fn observation_generic<const N: usize>(state: &State<N>) -> Observation {
+            state.observation(state.has_turn())
+        }
+        let state_enum: StateEnum = match self {
+            StateEnum::Players1(state) => state.determinize(rng).into(),
+            StateEnum::Players2(state) => state.determinize(rng).into(),
+            StateEnum::Players3(state) => state.determinize(rng).into(),
+            StateEnum::Players4(state) => state.determinize(rng).into(),
+        };
+        PyObservation {
+            observation: state_enum_repeat!(self, observation_generic),
+            state_enum,
+        }
+    }
+
+    /// Take an action in place. A state where the game has ended is kept, so it
+    /// can still be observed along with its `result()`.
+    pub(crate) fn take_action(&mut self, action: &Action) -> Option<GameResult> {
+        state_enum_repeat!(self, State::take_action_mut, action)
+    }
+
+    pub(crate) fn result(&self) -> Option<GameResult> {
+        state_enum_repeat!(self, State::result)
+    }
+
+    /// Take an action and return the increase in reward caused by it,
+    /// along with the result of the game if it has ended.
+    pub(crate) fn step(&mut self, action: &Action) -> (u8, Option<GameResult>) {
+        let reward_before = self.reward();
+        let result = self.take_action(action);
+        let reward_after = match result {
+            Some(result) => result.reward(),
+            None => self.reward(),
+        };
+        (reward_after.saturating_sub(reward_before), result)
+    }
+}
+
+impl From<State<1>> for StateEnum {
+    fn from(state: State<1>) -> Self {
+        StateEnum::Players1(state)
+    }
+}
+
+impl From<State<2>> for StateEnum {
+    fn from(state: State<2>) -> Self {
+        StateEnum::Players2(state)
+    }
+}
+
+impl From<State<3>> for StateEnum {
+    fn from(state: State<3>) -> Self {
+        StateEnum::Players3(state)
+    }
+}
+
+impl From<State<4>> for StateEnum {
+    fn from(state: State<4>) -> Self {
+        StateEnum::Players4(state)
+    }
+}
+
+impl std::fmt::Display for StateEnum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fn state_to_string<const N: usize>(state: &State<N>) -> String {
+            format!("{}", state)
+        }
+        write!(f, "{}", state_enum_repeat!(self, state_to_string))
+    }
+}
+
+#[derive(Clone, Debug)]
+#[pyclass(module = "regicide")]
+#[pyo3(name = "State")]
+pub struct PyState {
+    pub(crate) state_enum: StateEnum,
+}
+
+#[pymethods]
+impl PyState {
+    fn action_space(&self) -> Vec<PyAction> {
+        state_enum_repeat!(&self.state_enum, State::get_action_space)
+            .iter()
+            .map(|&a| a.into())
+            .collect()
+    }
+
+    /// A boolean array of length `ACTION_SPACE_SIZE` that is `True` at the
+    /// indices of legal actions.
+    fn action_mask<'py>(&self, py: Python<'py>) -> &'py PyArray1<bool> {
+        PyArray1::from_vec(py, self.state_enum.action_mask())
+    }
+
+    /// The index of an action in the fixed action space.
+    fn action_index(&self, action: PyAction) -> PyResult<usize> {
+        let action: Action = action.into();
+        state_enum_repeat!(&self.state_enum, State::action_index, &action).ok_or_else(|| {
+            PyValueError::new_err(format!("'{:?}' cannot be indexed in this state", action))
+        })
+    }
+
+    /// The legal action at an index of the fixed action space.
+    fn action_from_index(&self, index: usize) -> PyResult<PyAction> {
+        self.state_enum
+            .action_from_index(index)
+            .map(PyAction::from)
+            .ok_or_else(|| PyKeyError::new_err(format!("No legal action has index {}", index)))
+    }
+
+    /// Parse the notation of a legal action, e.g. `"AS+9C"` or `"discard[3D,5S]"`.
+    fn parse_action(&self, text: &str) -> PyResult<PyAction> {
+        state_enum_repeat!(&self.state_enum, State::parse_action, text)
+            .map(PyAction::from)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    fn has_turn(&self) -> PyResult<PlayerId> {
+        Ok(state_enum_repeat!(&self.state_enum, State::has_turn))
+    }
+
+    fn current_hand(&self) -> Vec<Card> {
+        state_enum_repeat!(&self.state_enum, State::current_hand)
+            .into_iter()
+            .collect()
+    }
+
+    fn reward(&self) -> u8 {
+        self.state_enum.reward()
+    }
+
+    /// The result of the game, `None` while it is still in progress.
+    fn result(&self) -> Option<PyGameResult> {
+        self.state_enum.result().map(PyGameResult::from)
+    }
+
+    /// The rating a solo victory would get with the refills used so far.
+    /// `None` in games with more than one player.
+    fn solo_outcome(&self) -> Option<SoloOutcome> {
+        self.state_enum.solo_outcome()
+    }
+
+    fn current_enemy(&self) -> PyResult<Option<Enemy>> {
+        Ok(state_enum_repeat!(&self.state_enum, State::current_enemy).copied())
+    }
+
+    /// Cards of the enemies left in the Castle deck, starting with the current enemy.
+    fn castle_deck(&self) -> Vec<Card> {
+        let castle_deck = self.state_enum.table().castle_deck();
+        castle_deck
+            .iter()
+            .rev()
+            .map(|enemy| *enemy.card())
+            .collect()
+    }
+
+    fn castle_deck_size(&self) -> usize {
+        self.state_enum.table().castle_deck().len()
+    }
+
+    fn tavern_deck_size(&self) -> usize {
+        self.state_enum.table().tavern_deck().len()
+    }
+
+    fn discard_pile(&self) -> Vec<Card> {
+        self.state_enum.table().discard_pile().to_vec()
+    }
+
+    /// Number of cards in the hand of every player, ordered by id.
+    fn hand_sizes(&self) -> Vec<usize> {
+        self.state_enum.hand_sizes()
+    }
+
+    /// Either `"play_cards"`, `"discard"` or `"jester"`.
+    fn phase(&self) -> &'static str {
+        phase_name(self.state_enum.action_type())
+    }
+
+    /// The damage that must be discarded when in the `"discard"` phase.
+    fn discard_amount(&self) -> Option<u8> {
+        discard_amount(self.state_enum.action_type())
+    }
+
+    fn times_yielded(&self) -> usize {
+        state_enum_repeat!(&self.state_enum, State::times_yielded)
+    }
+
+    fn hand_refills_left(&self) -> u8 {
+        state_enum_repeat!(&self.state_enum, State::hand_refills_left)
+    }
+
+    /// The rules the game is played with.
+    fn rules(&self) -> RuleSet {
+        self.state_enum.rules()
+    }
+
+    /// Number of enemies defeated. The same as `reward()`.
+    fn level(&self) -> u8 {
+        self.state_enum.reward()
+    }
+
+    /// Encode the state as seen by the player who has the turn into a flat
+    /// array of length `OBSERVATION_SIZE`.
+    fn encode<'py>(&self, py: Python<'py>) -> &'py PyArray1<f32> {
+        PyArray1::from_vec(py, self.state_enum.encode())
+    }
+
+    fn __str__(&self) -> String {
+        self.state_enum.to_string()
+    }
+
+    /// Serialize the full state, including hidden information, to JSON.
+    fn to_json(&self) -> PyResult<String> {
+        to_json(&self.state_enum)
+    }
+
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        Ok(Self {
+            state_enum: from_json(json)?,
+        })
+    }
+
+    /// Pickled through JSON, so requires the serde feature.
+    fn __reduce__(&self, py: Python) -> PyResult<(PyObject, (String,))> {
+        let from_json = py.get_type::<Self>().getattr("from_json")?;
+        Ok((from_json.into(), (self.to_json()?,)))
+    }
+}
+
+/// What a single player is allowed to see of the game.
+#[derive(Clone, Debug)]
+#[pyclass(module = "regicide")]
+#[pyo3(name = "Observation")]
+pub struct PyObservation {
+    observation: Observation,
+    /// A state consistent with the observation, where all hidden information
+    /// has been sampled at random. Only used by Rust players and never exposed to Python.
+    state_enum: StateEnum,
+}
+
+#[pymethods]
+impl PyObservation {
+    fn action_space(&self) -> Vec<PyAction> {
+        self.observation
+            .action_space()
+            .iter()
+            .map(|&a| a.into())
+            .collect()
+    }
+
+    /// A boolean array of length `ACTION_SPACE_SIZE` that is `True` at the
+    /// indices of legal actions.
+    fn action_mask<'py>(&self, py: Python<'py>) -> &'py PyArray1<bool> {
+        PyArray1::from_vec(py, self.observation.action_mask())
+    }
+
+    /// The index of an action in the fixed action space.
+    fn action_index(&self, action: PyAction) -> PyResult<usize> {
+        let action: Action = action.into();
+        self.observation.action_index(&action).ok_or_else(|| {
+            PyValueError::new_err(format!("'{:?}' cannot be indexed in this state", action))
+        })
+    }
+
+    /// The legal action at an index of the fixed action space.
+    fn action_from_index(&self, index: usize) -> PyResult<PyAction> {
+        self.observation
+            .action_from_index(index)
+            .map(PyAction::from)
+            .ok_or_else(|| PyKeyError::new_err(format!("No legal action has index {}", index)))
+    }
+
+    /// Parse the notation of a legal action, e.g. `"AS+9C"` or `"discard[3D,5S]"`.
+    fn parse_action(&self, text: &str) -> PyResult<PyAction> {
+        self.observation
+            .parse_action(text)
+            .map(PyAction::from)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// The player making the observation.
+    fn player(&self) -> PlayerId {
+        self.observation.player()
+    }
+
+    fn has_turn(&self) -> PlayerId {
+        self.observation.has_turn()
+    }
+
+    fn current_hand(&self) -> Vec<Card> {
+        self.observation.hand().into_iter().collect()
+    }
+
+    /// Number of cards in the hand of every player, ordered by id.
+    fn hand_sizes(&self) -> Vec<usize> {
+        self.observation.hand_sizes().to_vec()
+    }
+
+    fn reward(&self) -> u8 {
+        self.observation.reward()
+    }
+
+    fn current_enemy(&self) -> Option<Enemy> {
+        self.observation.current_enemy().copied()
+    }
+
+    /// Cards of the enemies left in the Castle deck, including the current
+    /// enemy. The order of the deck is hidden, so they are sorted by suit and value.
+    fn castle_deck(&self) -> Vec<Card> {
+        self.observation.castle_deck().to_vec()
+    }
+
+    fn castle_deck_size(&self) -> usize {
+        self.observation.castle_deck_size()
+    }
+
+    fn tavern_deck_size(&self) -> usize {
+        self.observation.tavern_deck_size()
+    }
+
+    fn discard_pile(&self) -> Vec<Card> {
+        self.observation.discard_pile().to_vec()
+    }
+
+    /// Either `"play_cards"`, `"discard"` or `"jester"`.
+    fn phase(&self) -> &'static str {
+        phase_name(self.observation.action_type())
+    }
+
+    /// The damage that must be discarded when in the `"discard"` phase.
+    fn discard_amount(&self) -> Option<u8> {
+        discard_amount(self.observation.action_type())
+    }
+
+    fn times_yielded(&self) -> usize {
+        self.observation.times_yielded()
+    }
+
+    fn hand_refills_left(&self) -> u8 {
+        self.observation.hand_refills_left()
+    }
+
+    /// Number of enemies defeated. The same as `reward()`.
+    fn level(&self) -> u8 {
+        self.observation.reward()
+    }
+
+    /// Encode the observation into a flat array of length `OBSERVATION_SIZE`.
+    fn encode<'py>(&self, py: Python<'py>) -> &'py PyArray1<f32> {
+        PyArray1::from_vec(py, self.observation.encode())
+    }
+
+    fn __str__(&self) -> String {
+        format!("{}", self.observation)
+    }
+}
+
+#[cfg(feature = "serde")]
+fn to_json<T: serde::Serialize>(value: &T) -> PyResult<String> {
+    serde_json::to_string(value).map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+#[cfg(feature = "serde")]
+fn from_json<T: serde::de::DeserializeOwned>(json: &str) -> PyResult<T> {
+    serde_json::from_str(json).map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+#[cfg(not(feature = "serde"))]
+fn to_json<T>(_value: &T) -> PyResult<String> {
+    Err(pyo3::exceptions::PyNotImplementedError::new_err(
+        "regicide was built without the serde feature",
+    ))
+}
+
+#[cfg(not(feature = "serde"))]
+fn from_json<T>(_json: &str) -> PyResult<T> {
+    Err(pyo3::exceptions::PyNotImplementedError::new_err(
+        "regicide was built without the serde feature",
+    ))
+}
+
+fn phase_name(action_type: ActionType) -> &'static str {
+    match action_type {
+        ActionType::PlayCards => "play_cards",
+        ActionType::Discard(_) => "discard",
+        ActionType::Jester => "jester",
+    }
+}
+
+fn discard_amount(action_type: ActionType) -> Option<u8> {
+    match action_type {
+        ActionType::Discard(amount) => Some(amount),
+        _ => None,
+    }
+}
+
+/// Anything a Rust player can be asked to act on from Python
+#[derive(Clone, FromPyObject)]
+pub enum PyGameView {
+    State(PyState),
+    Observation(PyObservation),
+}
+
+impl PyGameView {
+    pub(crate) fn state_enum(&self) -> StateEnum {
+        match self {
+            PyGameView::State(state) => state.state_enum,
+            PyGameView::Observation(observation) => observation.state_enum,
+        }
+    }
+}
+
+#[derive(Clone, FromPyObject)]
+enum RustPlayer {
+    RandomPlayer(RandomPlayer),
+    InputPlayer(InputPlayer),
+    MCTSPlayer(MCTSPlayer),
+    ISMCTSPlayer(ISMCTSPlayer),
+    PuctPlayer(PuctPlayer),
+}
+
+#[derive(Clone, FromPyObject)]
+enum PyPlayer {
+    Rust(RustPlayer),
+    Python(PyObject),
+}
+
+impl RustPlayer {
+    fn play_generic<const N: usize>(&mut self, state: State<N>) -> PyResult<Action> {
+        match self {
+            RustPlayer::RandomPlayer(player) => Ok(Play::play(player, state)),
+            RustPlayer::InputPlayer(player) => Ok(Play::play(player, state)),
+            RustPlayer::MCTSPlayer(player) => Ok(Play::play(player, state)),
+            RustPlayer::ISMCTSPlayer(player) => Ok(Play::play(player, state)),
+            RustPlayer::PuctPlayer(player) => player.try_play(state).map_err(search_error_to_py),
+        }
+    }
+
+    fn play(&mut self, state_enum: StateEnum) -> PyResult<Action> {
+        state_enum_repeat!(state_enum, |state| self.play_generic(state))
+    }
+}
+
+impl AsPyPointer for RustPlayer {
+    fn as_ptr(&self) -> *mut pyo3::ffi::PyObject {
+        todo!()
+    }
+}
+
+impl IntoPy<PyObject> for PyPlayer {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        match self {
+            PyPlayer::Rust(inner) => inner.into_py(py),
+            PyPlayer::Python(inner) => inner.into_py(py),
+        }
+    }
+}
+
+#[derive(Clone, FromPyObject)]
+pub enum PyAction {
+    PyActionPlay(PyActionPlay),
+    PyActionAnimalCombo(PyActionAnimalCombo),
+    PyActionCombo(PyActionCombo),
+    PyActionYield(PyActionYield),
+    PyActionDiscard(PyActionDiscard),
+    PyActionChangePlayer(PyActionChangePlayer),
+    PyActionRefillHand(PyActionRefillHand),
+}
+
+impl Into<Action> for PyAction {
+    fn into(self) -> Action {
+        match self {
+            PyAction::PyActionPlay(PyActionPlay(card)) => Action::Play(card),
+            PyAction::PyActionAnimalCombo(PyActionAnimalCombo(c1, c2)) => {
+                Action::AnimalCombo(c1, c2)
+            }
+            PyAction::PyActionYield(_) => Action::Yield,
+            PyAction::PyActionCombo(PyActionCombo(cards)) => Action::Combo(cards),
+            PyAction::PyActionDiscard(PyActionDiscard(hand)) => Action::Discard(hand),
+            PyAction::PyActionChangePlayer(PyActionChangePlayer(id)) => Action::ChangePlayer(id),
+            PyAction::PyActionRefillHand(PyActionRefillHand) => Action::RefillHand,
+        }
+    }
+}
+
+impl From<Action> for PyAction {
+    fn from(action: Action) -> Self {
+        match action {
+            Action::Play(card) => PyAction::PyActionPlay(PyActionPlay(card)),
+            Action::AnimalCombo(c1, c2) => {
+                PyAction::PyActionAnimalCombo(PyActionAnimalCombo(c1, c2))
+            }
+            Action::Yield => PyAction::PyActionYield(PyActionYield),
+            Action::Combo(cards) => PyAction::PyActionCombo(PyActionCombo(cards)),
+            Action::Discard(hand) => PyAction::PyActionDiscard(PyActionDiscard(hand)),
+            Action::ChangePlayer(id) => PyAction::PyActionChangePlayer(PyActionChangePlayer(id)),
+            Action::RefillHand => PyAction::PyActionRefillHand(PyActionRefillHand),
+        }
+    }
+}
+
+impl IntoPy<PyObject> for PyAction {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        match self {
+            PyAction::PyActionPlay(inner) => inner.into_py(py),
+            PyAction::PyActionAnimalCombo(inner) => inner.into_py(py),
+            PyAction::PyActionYield(inner) => inner.into_py(py),
+            PyAction::PyActionCombo(inner) => inner.into_py(py),
+            PyAction::PyActionDiscard(inner) => inner.into_py(py),
+            PyAction::PyActionChangePlayer(inner) => inner.into_py(py),
+            PyAction::PyActionRefillHand(inner) => inner.into_py(py),
+        }
+    }
+}
+
+/// Boilerplate implementation for a PyAction
+macro_rules! define_py_action {
+    (
+        $(#[$struct_meta:meta])*
+        struct $action_name:ident $(( $($field_type:ty),* ))?,
+        $(
+            $(#[$fn_meta:meta])*
+            fn $fn_name:ident($($arg:ident : $arg_type:ty),*) $(-> $fn_return_type:ty)?
+                $fn_body:block
+        )*
+    ) => {
+        #[derive(Clone, PartialEq)]
+        #[pyclass(module = "regicide.actions")]
+        $(#[$struct_meta])*
+        pub struct $action_name $(( $($field_type),* ))?;
+
+        #[pymethods]
+        impl $action_name {
+            $(
+                $(#[$fn_meta])*
+                fn $fn_name($($arg: $arg_type),*) $(-> $fn_return_type)? {
+                    $fn_body
+                }
+            )*
+
+            fn __str__(&self) -> String {
+                let action: Action = PyAction::$action_name(self.clone()).into();
+                format!("{:?}", action)
+            }
+
+            fn __repr__(&self) -> String {
+                let action: Action = PyAction::$action_name(self.clone()).into();
+                format!("{:?}", action)
+            }
+
+            /// The action in the notation understood by `parse_action()`.
+            fn notation(&self) -> String {
+                let action: Action = PyAction::$action_name(self.clone()).into();
+                action.to_string()
+            }
+
+            fn to_json(&self) -> PyResult<String> {
+                let action: Action = PyAction::$action_name(self.clone()).into();
+                to_json(&action)
+            }
+
+            #[staticmethod]
+            fn from_json(json: &str) -> PyResult<Self> {
+                let action: Action = from_json(json)?;
+                match PyAction::from(action) {
+                    PyAction::$action_name(inner) => Ok(inner),
+                    _ => Err(PyValueError::new_err(format!(
+                        "'{:?}' is not an {}",
+                        action,
+                        <Self as pyo3::type_object::PyTypeInfo>::NAME
+                    ))),
+                }
+            }
+
+            fn __reduce__(&self, py: Python) -> PyResult<(PyObject, (String,))> {
+                let from_json = py.get_type::<Self>().getattr("from_json")?;
+                Ok((from_json.into(), (self.to_json()?,)))
+            }
+
+            fn __richcmp__(&self, other: &Self, op: pyo3::basic::CompareOp) -> PyResult<bool> {
+                match op {
+                    pyo3::pyclass::CompareOp::Eq => Ok(self == other),
+                    pyo3::pyclass::CompareOp::Ne => Ok(self != other),
+                    _ => Err(PyTypeError::new_err("Operation not supported")),
+                }
+            }
+        }
+    }
+}
+
+define_py_action!(
+    #[pyo3(name = "ActionPlay")]
+    struct PyActionPlay(Card),
+    #[new]
+    fn new(card: Card) -> Self {
+        Self(card)
+    }
+);
+
+define_py_action!(
+    #[pyo3(name = "ActionAnimalCombo")]
+    struct PyActionAnimalCombo(Card, Card),
+    #[new]
+    fn new(c1: Card, c2: Card) -> Self {
+        Self(c1, c2)
+    }
+);
+
+define_py_action!(
+    #[pyo3(name = "ActionCombo")]
+    struct PyActionCombo(arrayvec::ArrayVecCopy<Card, 4>),
+    #[new]
+    fn new(cards: Vec<Card>) -> Self {
+        let cards_arr = arrayvec::ArrayVecCopy::from_iter(cards.into_iter());
+        Self(cards_arr)
+    }
+);
+
+define_py_action!(
+    #[pyo3(name = "ActionYield")]
+    struct PyActionYield,
+    #[new]
+    fn new() -> Self {
+        Self
+    }
+);
+
+define_py_action!(
+    #[pyo3(name = "ActionDiscard")]
+    struct PyActionDiscard(Hand),
+    #[new]
+    fn new(cards: Vec<Card>) -> Self {
+        let hand = Hand::from_iter(cards.into_iter());
+        Self(hand)
+    }
+);
+
+define_py_action!(
+    #[pyo3(name = "ActionChangePlayer")]
+    struct PyActionChangePlayer(PlayerId),
+    #[new]
+    fn new(id: usize) -> Self {
+        Self(PlayerId(id))
+    }
+);
+
+define_py_action!(
+    #[pyo3(name = "ActionRefillHand")]
+    struct PyActionRefillHand,
+    #[new]
+    fn new() -> Self {
+        Self
+    }
+);
+
+/// Parse the notation of an action, e.g. `"7H"`, `"AS+9C"`, `"discard[3D,5S]"`,
+/// `"yield"` or `"to:P2"`. Cards of combos and discards are kept in the order
+/// they are written, use `State.parse_action()` to get the matching legal action.
+#[pyfunction]
+fn parse_action(text: &str) -> PyResult<PyAction> {
+    text.parse::<Action>()
+        .map(PyAction::from)
+        .map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// Add `child` to `parent` and to `sys.modules`, so that it can be imported
+/// with `import regicide.<name>` and pickle can find its classes.
+fn add_submodule(py: Python, parent: &PyModule, child: &PyModule) -> PyResult<()> {
+    parent.add_submodule(child)?;
+    let name = format!("{}.{}", parent.name()?, child.name()?);
+    py.import("sys")?.getattr("modules")?.set_item(name, child)
+}
+
+/// A Python module implemented in Rust.
+#[pymodule]
+fn regicide(py: Python, m: &PyModule) -> PyResult<()> {
+    let card = PyModule::new(py, "card")?;
+    card.add_class::<Card>()?;
+    card.add_class::<CardSuit>()?;
+    card.add_class::<CardValue>()?;
+    add_submodule(py, m, card)?;
+
+    let actions = PyModule::new(py, "actions")?;
+    actions.add_class::<PyActionPlay>()?;
+    actions.add_class::<PyActionAnimalCombo>()?;
+    actions.add_class::<PyActionCombo>()?;
+    actions.add_class::<PyActionYield>()?;
+    actions.add_class::<PyActionDiscard>()?;
+    actions.add_class::<PyActionChangePlayer>()?;
+    actions.add_class::<PyActionRefillHand>()?;
+    add_submodule(py, m, actions)?;
+
+    let players = PyModule::new(py, "players")?;
+    players.add_class::<RandomPlayer>()?;
+    players.add_class::<InputPlayer>()?;
+    players.add_class::<MCTSPlayer>()?;
+    players.add_class::<ISMCTSPlayer>()?;
+    players.add_class::<PuctPlayer>()?;
+    add_submodule(py, m, players)?;
+
+    m.add_class::<RegicideGame>()?;
+    m.add_class::<PyState>()?;
+    m.add_class::<RuleSet>()?;
+    m.add_class::<SoloOutcome>()?;
+    m.add_class::<GameRecord>()?;
+    m.add_class::<RecordStep>()?;
+    m.add_class::<PyObservation>()?;
+    m.add_class::<VecRegicideEnv>()?;
+    m.add_class::<RegicideAECEnv>()?;
+    m.add_function(wrap_pyfunction!(parse_action, m)?)?;
+    m.add("OBSERVATION_SIZE", OBSERVATION_SIZE)?;
+    m.add("ACTION_SPACE_SIZE", ACTION_SPACE_SIZE)?;
+
+    Ok(())
+}
@@ -0,0 +1,140 @@
+//! Generate training data by letting a searching player play against itself.
+
+use crate::error::RegicideError;
+use crate::game::action_index::ACTION_SPACE_SIZE;
+use crate::game::encoding::OBSERVATION_SIZE;
+use crate::game::rules::RuleSet;
+use crate::game::state::State;
+use crate::game::{GameResult, GameStatus};
+use crate::players::Search;
+use std::io::Write;
+
+/// A single decision of a self-play game.
+#[derive(Debug, Clone)]
+pub struct SelfPlaySample {
+    /// The state encoded as seen by the player who had the turn, see `Observation::encode`.
+    pub observation: Vec<f32>,
+    /// Legal actions, indexed like the action space of `Action::index`.
+    pub mask: Vec<bool>,
+    /// Fraction of the visits of the search that went to each action.
+    pub policy: Vec<f32>,
+    /// How the game ended.
+    pub result: GameResult,
+}
+
+/// Play a full game with `player` choosing the actions of every player, and
+/// record the visit distribution of its search at every decision.
+pub fn self_play_game<const N_PLAYERS: usize, P: Search>(
+    player: &mut P,
+    seed: Option<u64>,
+    rules: RuleSet,
+) -> Result<Vec<SelfPlaySample>, RegicideError> {
+    let mut state = State::<N_PLAYERS>::with_rules(seed, rules)?;
+    let mut decisions = vec![];
+    let result = loop {
+        let action = player.play(state);
+
+        let ranked_actions = player.ranked_actions();
+        let total_visits: u64 = ranked_actions.iter().map(|&(_, visits, _)| visits).sum();
+        let mut policy = vec![0.0; ACTION_SPACE_SIZE];
+        for (action, visits, _) in ranked_actions {
+            if let Some(index) = state.action_index(action) {
+                policy[index] = *visits as f32 / total_visits.max(1) as f32;
+            }
+        }
+        decisions.push((state.encode(), state.action_mask(), policy));
+
+        state = match state.take_action(&action) {
+            GameStatus::InProgress(next_state) => next_state,
+            GameStatus::HasEnded(result) => break result,
+        };
+    };
+
+    Ok(decisions
+        .into_iter()
+        .map(|(observation, mask, policy)| SelfPlaySample {
+            observation,
+            mask,
+            policy,
+            result,
+        })
+        .collect())
+}
+
+/// Write the samples as a single `.npy` file holding a structured array, which
+/// can be read with `numpy.load(path)`. Each record has the fields:
+///
+/// - `observation`: float32 of shape `(OBSERVATION_SIZE,)`
+/// - `mask`: bool of shape `(ACTION_SPACE_SIZE,)`
+/// - `policy`: float32 of shape `(ACTION_SPACE_SIZE,)`
+/// - `reward`: uint8, the number of enemies defeated during the game
+/// - `won`: bool
+pub fn write_npy<W: Write>(samples: &[SelfPlaySample], mut writer: W) -> std::io::Result<()> {
+    let mut header = format!(
+        "{{'descr': [('observation', '<f4', ({},)), ('mask', '|b1', ({},)), \
+         ('policy', '<f4', ({},)), ('reward', '|u1'), ('won', '|b1')], \
+         'fortran_order': False, 'shape': ({},), }}",
+        OBSERVATION_SIZE,
+        ACTION_SPACE_SIZE,
+        ACTION_SPACE_SIZE,
+        samples.len()
+    );
+    // The magic string, version and header length take 10 bytes, and the
+    // data must start at a multiple of 64 bytes
+    let padding = 63 - (10 + header.len()) % 64;
+    header.push_str(&" ".repeat(padding));
+    header.push('\n');
+
+    writer.write_all(b"\x93NUMPY\x01\x00")?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+    for sample in samples {
+        for value in sample.observation.iter() {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        let mask = sample
+            .mask
+            .iter()
+            .map(|&legal| legal as u8)
+            .collect::<Vec<_>>();
+        writer.write_all(&mask)?;
+        for value in sample.policy.iter() {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        let won = matches!(sample.result, GameResult::Won(_));
+        writer.write_all(&[sample.result.reward(), won as u8])?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::rollout::Rollout;
+    use crate::players::ismcts_player::ISMCTSPlayer;
+
+    const SEED: u64 = 1337;
+
+    #[test]
+    fn self_play_records_visit_distribution() {
+        let mut player = ISMCTSPlayer::new(20, Rollout::Uniform, None, Some(SEED));
+        let samples =
+            self_play_game::<2, _>(&mut player, Some(SEED), RuleSet::official(2).unwrap()).unwrap();
+        for sample in samples.iter() {
+            let total: f32 = sample.policy.iter().sum();
+            assert!((total - 1.0).abs() < 1e-4);
+            for (legal, share) in sample.mask.iter().zip(sample.policy.iter()) {
+                assert!(*legal || *share == 0.0);
+            }
+        }
+
+        let mut file = vec![];
+        write_npy(&samples, &mut file).unwrap();
+        let header_len = u16::from_le_bytes([file[8], file[9]]) as usize;
+        assert_eq!((10 + header_len) % 64, 0);
+        let sample = &samples[0];
+        let record_size =
+            4 * (sample.observation.len() + sample.policy.len()) + sample.mask.len() + 2;
+        assert_eq!(file.len(), 10 + header_len + samples.len() * record_size);
+    }
+}
@@ -0,0 +1,212 @@
+use crate::game::encoding::OBSERVATION_SIZE;
+use crate::game::rules::RuleSet;
+use crate::python::{new_rng, rules_or_official, StateEnum};
+use itertools::Itertools;
+use numpy::{PyArray1, PyArray2};
+use pyo3::exceptions::{PyKeyError, PyValueError};
+use pyo3::prelude::*;
+use rand::prelude::StdRng;
+use rand::RngCore;
+
+type Observations<'py> = &'py PyArray2<f32>;
+type Rewards<'py> = &'py PyArray1<f32>;
+type Dones<'py> = &'py PyArray1<bool>;
+type Masks<'py> = &'py PyArray2<bool>;
+
+/// Runs many games of the same number of players side by side, stepping all
+/// of them in a single call. Actions are given as indices into the fixed
+/// action space, see `State.action_mask()`.
+#[pyclass(module = "regicide")]
+pub struct VecRegicideEnv {
+    states: Vec<StateEnum>,
+    num_players: usize,
+    rules: RuleSet,
+    num_threads: usize,
+    rng: StdRng,
+}
+
+struct StepResult {
+    /// The state after the action, or the first state of a new game
+    state: StateEnum,
+    observation: Vec<f32>,
+    reward: f32,
+    done: bool,
+    mask: Vec<bool>,
+}
+
+#[pymethods]
+impl VecRegicideEnv {
+    /// Games are stepped in parallel when `num_threads` is larger than 1.
+    /// `rules` defaults to the official rules for the number of players.
+    #[new]
+    fn new(
+        num_envs: usize,
+        num_players: usize,
+        seed: Option<u64>,
+        num_threads: Option<usize>,
+        rules: Option<RuleSet>,
+    ) -> PyResult<Self> {
+        if num_envs == 0 {
+            return Err(PyValueError::new_err(
+                "At least one environment is required",
+            ));
+        }
+        let rules = rules_or_official(num_players, rules)?;
+        let mut rng = new_rng(seed);
+        let states = (0..num_envs)
+            .map(|_| StateEnum::with_rules(num_players, Some(rng.next_u64()), rules))
+            .collect::<PyResult<_>>()?;
+        Ok(Self {
+            states,
+            num_players,
+            rules,
+            num_threads: num_threads.unwrap_or(1).max(1),
+            rng,
+        })
+    }
+
+    fn __len__(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Start new games in every environment.
+    ///
+    /// # Returns
+    /// Tuple in the form `(observations, masks)` with one row per environment
+    fn reset<'py>(
+        &mut self,
+        py: Python<'py>,
+        seed: Option<u64>,
+    ) -> PyResult<(Observations<'py>, Masks<'py>)> {
+        if seed.is_some() {
+            self.rng = new_rng(seed);
+        }
+        for state in self.states.iter_mut() {
+            *state =
+                StateEnum::with_rules(self.num_players, Some(self.rng.next_u64()), self.rules)?;
+        }
+
+        let observations = self.states.iter().map(StateEnum::encode).collect_vec();
+        let masks = self.states.iter().map(StateEnum::action_mask).collect_vec();
+        Ok((to_array2(py, &observations)?, to_array2(py, &masks)?))
+    }
+
+    /// Take one action in every environment. Games that end are reset
+    /// automatically, in which case the returned observation and mask belong
+    /// to the first state of the new game.
+    ///
+    /// # Returns
+    /// Tuple in the form `(observations, rewards, dones, masks)` with one row per environment
+    fn step<'py>(
+        &mut self,
+        py: Python<'py>,
+        actions: Vec<usize>,
+    ) -> PyResult<(Observations<'py>, Rewards<'py>, Dones<'py>, Masks<'py>)> {
+        if actions.len() != self.states.len() {
+            return Err(PyValueError::new_err(format!(
+                "Expected {} actions but got {}",
+                self.states.len(),
+                actions.len()
+            )));
+        }
+
+        // The random number generator only advances if the step succeeds
+        let mut rng = self.rng.clone();
+        let reset_seeds = (0..self.states.len()).map(|_| rng.next_u64()).collect_vec();
+        let (states, num_players, rules) = (&self.states, self.num_players, self.rules);
+        let num_threads = self.num_threads;
+        // Games are stepped on copies, so that none of them change if any action is illegal
+        let results = py
+            .allow_threads(|| {
+                parallel_map(states, num_threads, |i, state| {
+                    let action = state.action_from_index(actions[i]).ok_or_else(|| {
+                        PyKeyError::new_err(format!(
+                            "Action index {} is not legal in environment {}",
+                            actions[i], i
+                        ))
+                    })?;
+                    let mut state = *state;
+                    let (reward, result) = state.step(&action);
+                    if result.is_some() {
+                        state = StateEnum::with_rules(num_players, Some(reset_seeds[i]), rules)?;
+                    }
+                    Ok(StepResult {
+                        state,
+                        observation: state.encode(),
+                        reward: reward as f32,
+                        done: result.is_some(),
+                        mask: state.action_mask(),
+                    })
+                })
+            })
+            .into_iter()
+            .collect::<PyResult<Vec<_>>>()?;
+        for (state, result) in self.states.iter_mut().zip(results.iter()) {
+            *state = result.state;
+        }
+        self.rng = rng;
+
+        let observations = results.iter().map(|r| r.observation.clone()).collect_vec();
+        let rewards = results.iter().map(|r| r.reward).collect_vec();
+        let dones = results.iter().map(|r| r.done).collect_vec();
+        let masks = results.iter().map(|r| r.mask.clone()).collect_vec();
+        Ok((
+            to_array2(py, &observations)?,
+            PyArray1::from_vec(py, rewards),
+            PyArray1::from_vec(py, dones),
+            to_array2(py, &masks)?,
+        ))
+    }
+
+    /// Length of each row of the observations returned by `reset()` and `step()`.
+    #[staticmethod]
+    fn observation_size() -> usize {
+        OBSERVATION_SIZE
+    }
+}
+
+pub(crate) fn to_array2<'py, T: numpy::Element>(
+    py: Python<'py>,
+    rows: &[Vec<T>],
+) -> PyResult<&'py PyArray2<T>> {
+    PyArray2::from_vec2(py, rows).map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// Apply `f` to every item, split evenly across `num_threads` threads.
+/// `f` is given the index of the item along with the item itself.
+fn parallel_map<T, R, F>(items: &[T], num_threads: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(usize, &T) -> R + Sync,
+{
+    if num_threads <= 1 || items.len() <= 1 {
+        return items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| f(i, item))
+            .collect();
+    }
+
+    let chunk_size = (items.len() + num_threads - 1) / num_threads;
+    let f = &f;
+    std::thread::scope(|scope| {
+        let handles = items
+            .chunks(chunk_size)
+            .enumerate()
+            .map(|(chunk_index, chunk)| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .enumerate()
+                        .map(|(i, item)| f(chunk_index * chunk_size + i, item))
+                        .collect_vec()
+                })
+            })
+            .collect_vec();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("Environment thread panicked"))
+            .collect()
+    })
+}